@@ -0,0 +1,135 @@
+//! Command line arguments of the binary.
+
+use std::path::PathBuf;
+
+use crate::simulation::SimulationConfig;
+
+
+pub const USAGE: &str = "\
+Usage: blobs [options]
+
+Options:
+    --seed <seed>       Seed the simulation, to run it the same every time
+    --blobs <count>     How many random blobs to start with
+    --foods <count>     How many foods to start with
+    --names <path>      A file of blob names, separated by whitespace
+    --width <pixels>    The width of the window and the simulation
+    --height <pixels>   The height of the window and the simulation
+    --headless          Run without a window, and print statistics
+    --help              Print this message";
+
+/// The options given on the command line. Options that were not given
+/// are `None`, and keep their defaults.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args {
+    pub seed: Option<u64>,
+    pub blobs: Option<usize>,
+    pub foods: Option<usize>,
+    pub names: Option<PathBuf>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub headless: bool,
+    pub help: bool,
+}
+
+impl Args {
+    /// Parse the arguments, without the program name.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut ret = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--seed" => ret.seed = Some(parse_value(&arg, value()?)?),
+                "--blobs" => ret.blobs = Some(parse_value(&arg, value()?)?),
+                "--foods" => ret.foods = Some(parse_value(&arg, value()?)?),
+                "--names" => ret.names = Some(value()?.into()),
+                "--width" => ret.width = Some(parse_value(&arg, value()?)?),
+                "--height" => ret.height = Some(parse_value(&arg, value()?)?),
+                "--headless" => ret.headless = true,
+                "--help" | "-h" => ret.help = true,
+                _ => return Err(format!("unknown argument {}", arg)),
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Override the settings of `config` with the options that were given.
+    pub fn apply(&self, config: &mut SimulationConfig) {
+        if let Some(seed) = self.seed {
+            config.seed = Some(seed);
+        }
+        if let Some(blobs) = self.blobs {
+            config.initial_blobs = blobs;
+        }
+        if let Some(foods) = self.foods {
+            config.initial_foods = foods;
+        }
+        if let Some(width) = self.width {
+            config.size.x = width as f32;
+        }
+        if let Some(height) = self.height {
+            config.size.y = height as f32;
+        }
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(arg: &str, value: String) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value for {}: {}", arg, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use raylib::prelude::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse() {
+        let args = parse(&["--seed", "7", "--blobs", "3", "--names", "other.txt", "--headless"]).unwrap();
+        assert_eq!(args, Args {
+            seed: Some(7),
+            blobs: Some(3),
+            names: Some("other.txt".into()),
+            headless: true,
+            ..Args::default()
+        });
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&["--seed"]).is_err());
+        assert!(parse(&["--seed", "seven"]).is_err());
+        assert!(parse(&["--color", "red"]).is_err());
+    }
+
+    #[test]
+    fn test_apply_overrides_given_options() {
+        let defaults = SimulationConfig {
+            size: Vector2::new(1300., 680.),
+            initial_foods: 100,
+            ..SimulationConfig::default()
+        };
+
+        let mut config = defaults;
+        parse(&[]).unwrap().apply(&mut config);
+        assert_eq!(config.size, defaults.size);
+        assert_eq!(config.seed, None);
+        assert_eq!(config.initial_foods, 100);
+
+        let mut config = defaults;
+        parse(&["--seed", "7", "--foods", "20", "--width", "400"]).unwrap().apply(&mut config);
+        assert_eq!(config.size, Vector2::new(400., 680.));
+        assert_eq!(config.seed, Some(7));
+        assert_eq!(config.initial_foods, 20);
+        assert_eq!(config.initial_blobs, defaults.initial_blobs);
+
+        let mut config = defaults;
+        parse(&["--blobs", "3"]).unwrap().apply(&mut config);
+        assert_eq!(config.initial_blobs, 3);
+    }
+}
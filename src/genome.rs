@@ -0,0 +1,292 @@
+//! Heritable properties of blobs.
+//!
+//! A genome is passed from a blob to its children when it
+//! reproduces. Children get a mutated copy of the genome,
+//! which is what lets natural selection take place.
+
+use rand::prelude::*;
+
+use raylib::prelude::*;
+
+use crate::{
+    simulation::Food,
+    steering::Brain,
+    math::gaussian,
+};
+
+
+/// Returns one of the values, each with the same chance.
+fn pick<T, R: Rng + ?Sized>(rng: &mut R, a: T, b: T) -> T {
+    if rng.gen() { a } else { b }
+}
+
+/// How a blob chooses where to go from what it sees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub enum Steering {
+    /// Toward the things it sees in colors it likes, and away from
+    /// those in colors it does not.
+    ColorAverage,
+    /// Straight to the nearest food it sees, or like `ColorAverage`
+    /// when it sees no food.
+    NearestFood,
+    /// Wherever its brain says.
+    Neural,
+}
+
+impl Steering {
+    pub const ALL: [Steering; 3] = [Steering::ColorAverage, Steering::NearestFood, Steering::Neural];
+}
+
+/// Which blobs a blob attacks when they touch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub enum Role {
+    /// Attacks every blob it touches.
+    Predator,
+    /// Attacks no blob, so prey only fight predators.
+    Prey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct Genome {
+    pub speed: f32,
+    pub rotation_speed: f32,
+    /// The radius the blob is born with.
+    pub radius: f32,
+    /// The radius the blob can grow up to by eating.
+    pub max_radius: f32,
+
+    pub pov: f32,
+    pub sight_depth: f32,
+    pub favorite_color: Color,
+    pub color_attraction: f32,
+    pub color_repulsion: f32,
+    pub steering: Steering,
+    /// Used by the neural steering.
+    pub brain: Brain,
+    pub role: Role,
+
+    pub max_hunger: f32,
+    /// Multiplies how fast the blob gets hungry.
+    pub hunger_rate: f32,
+    pub attack: f32,
+    pub defence: f32,
+    pub hunger_reduction: f32,
+    pub hunger_division: f32,
+
+    /// How many seconds the blob lives before dying of old age.
+    pub max_lifespan: f32,
+}
+
+impl Genome {
+    /// The smallest radius a mutation can shrink a blob to.
+    pub const MIN_RADIUS: f32 = 1.;
+    /// The widest angle of sight, in degrees.
+    pub const MAX_POV: f32 = 360.;
+
+    /// Returns a copy of the genome with every gene slightly changed.
+    ///
+    /// Each numeric gene is scaled by a gaussian factor around 1
+    /// with a standard deviation of `rate`, and then clamped to
+    /// its valid range. Each channel of the favorite color moves
+    /// by a gaussian with a standard deviation of `rate * 255`.
+    /// The steering switches to another kind with a chance of `rate`,
+    /// and the brain's weights move by gaussians with a standard
+    /// deviation of `rate`. The role switches with a chance of `rate`.
+    pub fn mutate<R: Rng + ?Sized>(&self, rng: &mut R, rate: f32) -> Genome {
+        debug_assert!(rate >= 0.);
+
+        let mut gene = |value: f32, min: f32, max: f32| {
+            (value * (1. + rate * gaussian(rng))).max(min).min(max)
+        };
+        let speed = gene(self.speed, 0., f32::INFINITY);
+        let rotation_speed = gene(self.rotation_speed, 0., f32::INFINITY);
+        let radius = gene(self.radius, Self::MIN_RADIUS, f32::INFINITY);
+        let max_radius = gene(self.max_radius, Self::MIN_RADIUS, f32::INFINITY);
+        let pov = gene(self.pov, 0., Self::MAX_POV);
+        let sight_depth = gene(self.sight_depth, 0., f32::INFINITY);
+        let color_attraction = gene(self.color_attraction, 0., 1.);
+        let color_repulsion = gene(self.color_repulsion, 0., 1.);
+        let max_hunger = gene(self.max_hunger, 0., f32::INFINITY);
+        let hunger_rate = gene(self.hunger_rate, 0., f32::INFINITY);
+        let attack = gene(self.attack, 0., f32::INFINITY);
+        let defence = gene(self.defence, 0., f32::INFINITY);
+        let hunger_reduction = gene(self.hunger_reduction, 0., 1.);
+        let hunger_division = gene(self.hunger_division, 0., f32::INFINITY);
+        let max_lifespan = gene(self.max_lifespan, 0., f32::INFINITY);
+
+        let mut channel = |value: u8| {
+            (value as f32 + rate * 255. * gaussian(rng)).round().max(0.).min(255.) as u8
+        };
+        let c = self.favorite_color;
+        let favorite_color = Color::new(channel(c.r), channel(c.g), channel(c.b), c.a);
+
+        let steering = if rng.gen::<f32>() < rate {
+            let others: Vec<_> = Steering::ALL.iter().filter(|&&steering| steering != self.steering).collect();
+            **others.choose(rng).unwrap()
+        } else {
+            self.steering
+        };
+        let brain = self.brain.mutate(rng, rate);
+        let role = if rng.gen::<f32>() < rate {
+            match self.role {
+                Role::Predator => Role::Prey,
+                Role::Prey => Role::Predator,
+            }
+        } else {
+            self.role
+        };
+
+        Genome {
+            speed, rotation_speed, radius, max_radius,
+            pov, sight_depth,
+            favorite_color,
+            color_attraction, color_repulsion,
+            steering,
+            brain,
+            role,
+            max_hunger, hunger_rate,
+            attack, defence,
+            hunger_reduction, hunger_division,
+            max_lifespan,
+        }
+    }
+
+    /// Returns a genome that takes each gene from one of the parents.
+    ///
+    /// The genes are not mutated; children of two parents are
+    /// usually `crossover` and then `mutate`.
+    pub fn crossover<R: Rng + ?Sized>(&self, other: &Genome, rng: &mut R) -> Genome {
+        Genome {
+            speed: pick(rng, self.speed, other.speed),
+            rotation_speed: pick(rng, self.rotation_speed, other.rotation_speed),
+            radius: pick(rng, self.radius, other.radius),
+            max_radius: pick(rng, self.max_radius, other.max_radius),
+            pov: pick(rng, self.pov, other.pov),
+            sight_depth: pick(rng, self.sight_depth, other.sight_depth),
+            favorite_color: pick(rng, self.favorite_color, other.favorite_color),
+            color_attraction: pick(rng, self.color_attraction, other.color_attraction),
+            color_repulsion: pick(rng, self.color_repulsion, other.color_repulsion),
+            steering: pick(rng, self.steering, other.steering),
+            brain: self.brain.crossover(&other.brain, rng),
+            role: pick(rng, self.role, other.role),
+            max_hunger: pick(rng, self.max_hunger, other.max_hunger),
+            hunger_rate: pick(rng, self.hunger_rate, other.hunger_rate),
+            attack: pick(rng, self.attack, other.attack),
+            defence: pick(rng, self.defence, other.defence),
+            hunger_reduction: pick(rng, self.hunger_reduction, other.hunger_reduction),
+            hunger_division: pick(rng, self.hunger_division, other.hunger_division),
+            max_lifespan: pick(rng, self.max_lifespan, other.max_lifespan),
+        }
+    }
+}
+
+impl Default for Genome {
+    fn default() -> Self {
+        Self {
+            speed: 60.,
+            rotation_speed: 2.5,
+            radius: 10.,
+            max_radius: 20.,
+            pov: 90.,
+            sight_depth: 85.,
+            favorite_color: Food::COLOR,
+            color_attraction: 0.5,
+            color_repulsion: 0.5,
+            steering: Steering::ColorAverage,
+            brain: Brain::default(),
+            //  every blob attacks every other, unless it is made prey
+            role: Role::Predator,
+            max_hunger: 12.5,
+            hunger_rate: 1.,
+            attack: 0.5,
+            defence: 1.,
+            hunger_reduction: 0.25,
+            hunger_division: 0.5,
+            max_lifespan: 120.,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_mutate_is_reproducible() {
+        let genome = Genome::default();
+        let a = genome.mutate(&mut StdRng::seed_from_u64(7), 0.1);
+        let b = genome.mutate(&mut StdRng::seed_from_u64(7), 0.1);
+        assert_eq!(a, b);
+        assert_ne!(a, genome);
+    }
+
+    #[test]
+    fn test_mutate_zero_rate_keeps_genes() {
+        let genome = Genome::default();
+        assert_eq!(genome.mutate(&mut StdRng::seed_from_u64(7), 0.), genome);
+    }
+
+    #[test]
+    fn test_mutate_stays_in_bounds() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut genome = Genome::default();
+        for _ in 0..1000 {
+            //  a large rate, so genes hit their bounds
+            genome = genome.mutate(&mut rng, 2.);
+            assert!(genome.speed >= 0.);
+            assert!(genome.rotation_speed >= 0.);
+            assert!(genome.radius >= Genome::MIN_RADIUS);
+            assert!(genome.max_radius >= Genome::MIN_RADIUS);
+            assert!(0. <= genome.pov && genome.pov <= Genome::MAX_POV);
+            assert!(genome.sight_depth >= 0.);
+            assert!(0. <= genome.color_attraction && genome.color_attraction <= 1.);
+            assert!(0. <= genome.color_repulsion && genome.color_repulsion <= 1.);
+            assert!(genome.max_hunger >= 0.);
+            assert!(genome.hunger_rate >= 0.);
+            assert!(genome.attack >= 0.);
+            assert!(genome.defence >= 0.);
+            assert!(0. <= genome.hunger_reduction && genome.hunger_reduction <= 1.);
+            assert!(genome.hunger_division >= 0.);
+            assert!(genome.max_lifespan >= 0.);
+            assert_eq!(genome.favorite_color.a, Food::COLOR.a);
+        }
+    }
+
+    #[test]
+    fn test_crossover_picks_parent_genes() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let a = Genome::default();
+        let b = a.mutate(&mut rng, 0.5);
+        for _ in 0..100 {
+            let child = a.crossover(&b, &mut rng);
+            let from_parent = |gene: fn(&Genome) -> f32| gene(&child) == gene(&a) || gene(&child) == gene(&b);
+            assert!(from_parent(|g| g.speed));
+            assert!(from_parent(|g| g.rotation_speed));
+            assert!(from_parent(|g| g.radius));
+            assert!(from_parent(|g| g.max_radius));
+            assert!(from_parent(|g| g.pov));
+            assert!(from_parent(|g| g.sight_depth));
+            assert!(from_parent(|g| g.color_attraction));
+            assert!(from_parent(|g| g.color_repulsion));
+            assert!(from_parent(|g| g.max_hunger));
+            assert!(from_parent(|g| g.hunger_rate));
+            assert!(from_parent(|g| g.attack));
+            assert!(from_parent(|g| g.defence));
+            assert!(from_parent(|g| g.hunger_reduction));
+            assert!(from_parent(|g| g.hunger_division));
+            assert!(from_parent(|g| g.max_lifespan));
+            assert!(child.favorite_color == a.favorite_color || child.favorite_color == b.favorite_color);
+            assert!(child.steering == a.steering || child.steering == b.steering);
+            assert!(child.role == a.role || child.role == b.role);
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::{Genome, Steering, Role};
+}
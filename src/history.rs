@@ -0,0 +1,102 @@
+//! A time series of a simulation's population, for plotting.
+
+use std::io::{self, Write};
+
+use crate::stats::Stats;
+
+
+/// The population of a simulation after one step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryRow {
+    /// How many steps the simulation had taken.
+    pub tick: u64,
+    pub blob_count: usize,
+    pub food_count: usize,
+    pub mean_speed: f32,
+    pub mean_pov: f32,
+    pub mean_attack: f32,
+    pub mean_defence: f32,
+}
+
+impl HistoryRow {
+    pub fn new(tick: u64, stats: &Stats) -> Self {
+        Self {
+            tick,
+            blob_count: stats.blob_count,
+            food_count: stats.food_count,
+            mean_speed: stats.speed.mean,
+            mean_pov: stats.pov.mean,
+            mean_attack: stats.attack.mean,
+            mean_defence: stats.defence.mean,
+        }
+    }
+}
+
+/// Records a row every time the simulation steps.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryRecorder {
+    rows: Vec<HistoryRow>,
+}
+
+impl HistoryRecorder {
+    const HEADER: &'static str = "tick,blob_count,food_count,mean_speed,mean_pov,mean_attack,mean_defence";
+
+    pub fn new() -> Self { Self::default() }
+
+    pub fn record(&mut self, row: HistoryRow) { self.rows.push(row); }
+
+    /// Returns the rows, oldest first.
+    pub fn rows(&self) -> &[HistoryRow] { &self.rows }
+
+    pub fn clear(&mut self) { self.rows.clear(); }
+
+    /// Write a header and then a line for every row.
+    pub fn write_csv<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "{}", Self::HEADER)?;
+        for row in &self.rows {
+            writeln!(w, "{},{},{},{},{},{},{}",
+                row.tick, row.blob_count, row.food_count,
+                row.mean_speed, row.mean_pov, row.mean_attack, row.mean_defence)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use raylib::prelude::*;
+
+    use crate::{simulation::prelude::*, genome::Genome};
+
+    #[test]
+    fn test_write_csv() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        sim.history = Some(HistoryRecorder::new());
+        sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)));
+        sim.insert_food(Vector2::new(5., 195.));
+        for _ in 0..5 { sim.step(0.1); }
+
+        let mut buffer = vec![];
+        sim.history.as_ref().unwrap().write_csv(&mut buffer).unwrap();
+        let csv = String::from_utf8(buffer).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(HistoryRecorder::HEADER));
+        let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows.len(), 5);
+        for (i, row) in rows.iter().enumerate() {
+            assert_eq!(row.len(), 7);
+            assert_eq!(row[0].parse::<u64>().unwrap(), i as u64 + 1);
+            assert_eq!(row[1], "1");
+            assert_eq!(row[2], "1");
+            assert_eq!(row[3].parse::<f32>().unwrap(), Genome::default().speed);
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::{HistoryRecorder, HistoryRow};
+}
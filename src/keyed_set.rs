@@ -1,7 +1,8 @@
 //! A set of elements that are given unique keys.
 //!
 //! A `KeyedSet<T>` has all the properties of a `HashMap<Key, T>`
-//! but the keys are generated by the data structure.
+//! but the keys are generated by the data structure, and
+//! iteration follows insertion order.
 //!
 //! # Example
 //! 
@@ -17,24 +18,48 @@
 
 use std::{
     collections::{
+        BTreeMap,
         HashMap,
-        hash_map,
+        btree_map,
     },
     fmt::Display,
+    iter::FromIterator,
     marker::PhantomData,
+    ops::{Index, IndexMut},
 };
 
 /// A key is like a reference to an element of a keyed set.
 ///
 /// The lifetime of the key is the lifetime of the creating 
-/// `KeyedSet`. The time parameter is the element type of
-/// the `KeyedSet`.
+/// `KeyedSet`. The type parameter is the element type of
+/// the `KeyedSet`, so a `Key<Blob>` can not be used where
+/// a `Key<Food>` is expected.
+///
+/// Indices of removed elements are reused by later inserts,
+/// so a key also carries the generation of its slot. A key
+/// whose generation is stale does not refer to any element.
 #[derive(Debug)]
-pub struct Key<T>(usize, PhantomData<*const T>);
+pub struct Key<T> {
+    index: usize,
+    generation: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Key<T> {
+    fn new(index: usize, generation: u32) -> Self {
+        Self { index, generation, _marker: PhantomData }
+    }
+
+    /// The slot of the element in the set.
+    pub fn index(&self) -> usize { self.index }
+
+    /// How many times the slot was reused before this key.
+    pub fn generation(&self) -> u32 { self.generation }
+}
 
 impl<T> PartialEq for Key<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.0.eq(&other.0)
+        (self.index, self.generation).eq(&(other.index, other.generation))
     }
 } 
 
@@ -42,13 +67,14 @@ impl<T> Eq for Key<T> {}
 
 impl<T> std::hash::Hash for Key<T> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.0.hash(state)
+        self.index.hash(state);
+        self.generation.hash(state);
     }
 }
 
 impl<T> Clone for Key<T> {
     fn clone(&self) -> Self {
-        Self(self.0, self.1)
+        *self
     }
 }
 
@@ -56,20 +82,21 @@ impl<T> Copy for Key<T> {}
 
 impl<T> PartialOrd for Key<T> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.0.partial_cmp(&other.0)
+        Some(self.cmp(other))
     }
 }
 
 impl<T> Ord for Key<T> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.0.cmp(&other.0)
+        (self.index, self.generation).cmp(&(other.index, other.generation))
     }
 }
 
 /// A set of elements that are given unique keys.
 ///
 /// A `KeyedSet<T>` has all the properties of a `HashMap<Key, T>`
-/// but the keys are generated by the data structure.
+/// but the keys are generated by the data structure, and
+/// iteration follows insertion order.
 ///
 /// # Example
 /// 
@@ -83,80 +110,399 @@ impl<T> Ord for Key<T> {
 /// assert_eq!(set.get(hi_key), None);
 /// ```
 pub struct KeyedSet<T> {
-    map: HashMap<Key<T>, T>,
-    next: Key<T>,
+    //  elements by insertion order, so iteration is deterministic
+    entries: BTreeMap<u64, (Key<T>, T)>,
+    //  the insertion order of every element by key
+    positions: HashMap<Key<T>, u64>,
+    //  the insertion order of the next element
+    inserted: u64,
+    //  the current generation of every slot ever handed out
+    generations: Vec<u32>,
+    //  slots of removed elements, waiting to be reused
+    free: Vec<usize>,
+    //  the first slot that was never handed out
+    next: usize,
 }
 
 impl<T> KeyedSet<T> {
     pub fn new() -> Self {
-        Self { map: HashMap::new(), next: Key(0, PhantomData) }
+        Self::with_capacity(0)
+    }
+
+    /// Create an empty set with room for at least `cap` elements.
+    pub fn with_capacity(cap: usize) -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            positions: HashMap::with_capacity(cap),
+            inserted: 0,
+            generations: Vec::with_capacity(cap),
+            free: vec![],
+            next: 0,
+        }
     }
 
+    /// Make room for at least `additional` more elements.
+    pub fn reserve(&mut self, additional: usize) {
+        self.positions.reserve(additional);
+        self.generations.reserve(additional.saturating_sub(self.free.len()));
+    }
+
+    pub fn capacity(&self) -> usize { self.positions.capacity() }
+
     fn generate_key(&mut self) -> Key<T> {
-        let key = self.next;
-        self.next.0 += 1;
-        key
+        if let Some(index) = self.free.pop() {
+            Key::new(index, self.generations[index])
+        } else {
+            let index = self.next;
+            self.next += 1;
+            self.generations.push(0);
+            Key::new(index, 0)
+        }
+    }
+
+    //  put an element last in the insertion order
+    fn insert_at(&mut self, key: Key<T>, value: T) {
+        let position = self.inserted;
+        self.inserted += 1;
+        self.entries.insert(position, (key, value));
+        self.positions.insert(key, position);
+    }
+
+    //  bump the generation of a removed element's slot and free it
+    fn free_slot(generations: &mut [u32], free: &mut Vec<usize>, key: Key<T>) {
+        generations[key.index] += 1;
+        free.push(key.index);
     }
     
     pub fn insert(&mut self, value: T) -> Key<T> {
         let key = self.generate_key();
-        self.map.insert(key, value);
+        self.insert_at(key, value);
         key
     }
 
+    /// Get the element of `key`, inserting `f()` under that exact key
+    /// if it is vacant.
+    ///
+    /// A key refers to a slot and a generation. Inserting takes the
+    /// slot over at the key's generation, so later inserts never hand
+    /// out `key` again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is stale, meaning its slot has moved on to a
+    /// newer generation. Inserting under it would alias newer keys.
+    /// Also panics if another generation of the slot is in the set,
+    /// since a slot holds a single element.
+    pub fn get_or_insert_with<F: FnOnce() -> T>(&mut self, key: Key<T>, f: F) -> &mut T {
+        if !self.positions.contains_key(&key) {
+            if key.index < self.next {
+                let live = Key::new(key.index, self.generations[key.index]);
+                assert!(!self.positions.contains_key(&live), "slot {} is taken by generation {}", key, live.generation);
+            }
+            //  grow the slots up to the key, freeing the skipped ones
+            while self.next <= key.index {
+                self.generations.push(0);
+                self.free.push(self.next);
+                self.next += 1;
+            }
+            let generation = &mut self.generations[key.index];
+            assert!(key.generation >= *generation, "stale key {}", key);
+            *generation = key.generation;
+            self.free.retain(|&index| index != key.index);
+            self.insert_at(key, f());
+        }
+        &mut self[key]
+    }
+
     pub fn get(&self, key: Key<T>) -> Option<&T> {
-        self.map.get(&key)
+        let position = self.positions.get(&key)?;
+        self.entries.get(position).map(|(_, value)| value)
     }
     
     pub fn get_mut(&mut self, key: Key<T>) -> Option<&mut T> {
-        self.map.get_mut(&key)
+        let position = self.positions.get(&key)?;
+        self.entries.get_mut(position).map(|(_, value)| value)
+    }
+
+    /// Get mutable references to two different elements at once.
+    ///
+    /// Returns `None` if `a == b` or either key is missing.
+    pub fn get_pair_mut(&mut self, a: Key<T>, b: Key<T>) -> Option<(&mut T, &mut T)> {
+        if a == b { return None; }
+        let position_a = *self.positions.get(&a)?;
+        let position_b = *self.positions.get(&b)?;
+        //  the two elements are the ends of the range between them
+        let mut range = self.entries.range_mut(position_a.min(position_b)..=position_a.max(position_b));
+        let (_, (_, first)) = range.next()?;
+        let (_, (_, last)) = range.next_back()?;
+        if position_a < position_b {
+            Some((first, last))
+        } else {
+            Some((last, first))
+        }
     }
 
+    /// Remove an element and free its slot for reuse.
+    ///
+    /// The generation of the slot is bumped so `key` and any
+    /// copies of it go stale.
     pub fn remove(&mut self, key: Key<T>) -> Option<T> {
-        self.map.remove(&key)
+        let position = self.positions.remove(&key)?;
+        let (_, value) = self.entries.remove(&position)?;
+        Self::free_slot(&mut self.generations, &mut self.free, key);
+        Some(value)
+    }
+
+    /// Keep only the elements for which `f` returns true.
+    ///
+    /// `f` is called once for every element, with its key.
+    pub fn retain<F: FnMut(Key<T>, &T) -> bool>(&mut self, mut f: F) {
+        let Self { entries, positions, generations, free, .. } = self;
+        entries.retain(|_, (key, value)| {
+            let keep = f(*key, value);
+            if !keep {
+                positions.remove(key);
+                Self::free_slot(generations, free, *key);
+            }
+            keep
+        });
     }
 
+    /// Iterate over the elements in insertion order.
     pub fn iter(&self) -> <&Self as IntoIterator>::IntoIter {
         self.into_iter()
     }
 
+    /// Iterate over the elements in insertion order.
     pub fn iter_mut(&mut self) -> <&mut Self as IntoIterator>::IntoIter {
         self.into_iter()
     }
 
-    pub fn len(&self) -> usize { self.map.len() }
+    pub fn keys(&self) -> impl Iterator<Item = Key<T>> + '_ {
+        self.entries.values().map(|(key, _)| *key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.entries.values().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.entries.values_mut().map(|(_, value)| value)
+    }
+
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    /// Remove every element.
+    ///
+    /// Keys stay monotonic: every freed slot gets a new generation,
+    /// so keys from before the clear never alias new elements.
+    pub fn clear(&mut self) {
+        self.drain();
+    }
+
+    /// Remove every element, yielding them in insertion order.
+    ///
+    /// The set is empty as soon as this returns, even if the iterator
+    /// is not consumed. Like `clear`, the drained keys never alias
+    /// elements inserted later.
+    pub fn drain(&mut self) -> IntoIter<T> {
+        let entries = std::mem::take(&mut self.entries);
+        for (key, _) in entries.values() {
+            Self::free_slot(&mut self.generations, &mut self.free, *key);
+        }
+        self.positions.clear();
+        IntoIter(entries.into_values())
+    }
+}
+
+/// Collect values into a set, generating a fresh key for each.
+///
+/// The generated keys are not returned. Use `insert` to keep them.
+impl<T> FromIterator<T> for KeyedSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+/// Insert values into a set, generating a fresh key for each.
+///
+/// The generated keys are not returned. Use `insert` to keep them.
+impl<T> Extend<T> for KeyedSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T> Index<Key<T>> for KeyedSet<T> {
+    type Output = T;
+
+    /// # Panics
+    ///
+    /// Panics if `key` is not in the set.
+    fn index(&self, key: Key<T>) -> &T {
+        self.get(key).unwrap_or_else(|| panic!("key {} is not in the set", key))
+    }
+}
+
+impl<T> IndexMut<Key<T>> for KeyedSet<T> {
+    /// # Panics
+    ///
+    /// Panics if `key` is not in the set.
+    fn index_mut(&mut self, key: Key<T>) -> &mut T {
+        self.get_mut(key).unwrap_or_else(|| panic!("key {} is not in the set", key))
+    }
+}
+
+/// An owning iterator over a keyed set, in insertion order.
+pub struct IntoIter<T>(btree_map::IntoValues<u64, (Key<T>, T)>);
+
+/// An iterator over a keyed set, in insertion order.
+pub struct Iter<'a, T>(btree_map::Values<'a, u64, (Key<T>, T)>);
+
+/// A mutable iterator over a keyed set, in insertion order.
+pub struct IterMut<'a, T>(btree_map::ValuesMut<'a, u64, (Key<T>, T)>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = (Key<T>, T);
+
+    fn next(&mut self) -> Option<Self::Item> { self.0.next() }
+
+    fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (&'a Key<T>, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = (&'a Key<T>, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
 }
 
 impl<T> IntoIterator for KeyedSet<T> {
     type Item = (Key<T>, T);
-    type IntoIter = hash_map::IntoIter<Key<T>, T>;
+    type IntoIter = IntoIter<T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.map.into_iter()
+        IntoIter(self.entries.into_values())
     }
 }
 
 impl<'a, T> IntoIterator for &'a KeyedSet<T> {
     type Item = (&'a Key<T>, &'a T);
-    type IntoIter = hash_map::Iter<'a, Key<T>, T>;
+    type IntoIter = Iter<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.map.iter()
+        Iter(self.entries.values())
     }
 }
 
 impl<'a, T> IntoIterator for &'a mut KeyedSet<T> {
     type Item = (&'a Key<T>, &'a mut T);
-    type IntoIter = hash_map::IterMut<'a, Key<T>, T>;
+    type IntoIter = IterMut<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.map.iter_mut()
+        IterMut(self.entries.values_mut())
     }
 }
 
 impl<T> Display for Key<T> {
+    /// Prints `#N`, the slot of the key, whatever its generation.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("#{}{}", std::any::type_name::<T>(), self.0))
+        f.write_fmt(format_args!("#{}", self.index))
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use serde::{
+        de::Error,
+        Deserialize, Deserializer, Serialize, Serializer,
+    };
+
+    use super::{Key, KeyedSet};
+
+    impl<T> Serialize for Key<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            (self.index, self.generation).serialize(serializer)
+        }
+    }
+
+    impl<'de, T> Deserialize<'de> for Key<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let (index, generation) = <(usize, u32)>::deserialize(deserializer)?;
+            Ok(Key::new(index, generation))
+        }
+    }
+
+    //  the serialized form of a keyed set
+    #[derive(Serialize)]
+    struct SetRef<'a, T> {
+        entries: Vec<(Key<T>, &'a T)>,
+        generations: &'a [u32],
+        next: usize,
+    }
+
+    #[derive(Deserialize)]
+    struct SetOwned<T> {
+        entries: Vec<(Key<T>, T)>,
+        generations: Vec<u32>,
+        next: usize,
+    }
+
+    impl<T: Serialize> Serialize for KeyedSet<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            SetRef {
+                entries: self.iter().map(|(&key, value)| (key, value)).collect(),
+                generations: &self.generations,
+                next: self.next,
+            }.serialize(serializer)
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for KeyedSet<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let SetOwned { entries, generations, next } = SetOwned::deserialize(deserializer)?;
+            if generations.len() != next {
+                return Err(D::Error::custom("generations do not match next"));
+            }
+
+            let mut set = KeyedSet::with_capacity(entries.len());
+            let mut live = vec![false; next];
+            for (key, value) in entries {
+                if key.index >= next {
+                    return Err(D::Error::custom(format!("key {} is not below next", key)));
+                }
+                if key.generation != generations[key.index] || live[key.index] {
+                    return Err(D::Error::custom(format!("key {} is stale or repeated", key)));
+                }
+                live[key.index] = true;
+                set.insert_at(key, value);
+            }
+            set.free = (0..next).rev().filter(|&index| !live[index]).collect();
+            set.generations = generations;
+            set.next = next;
+            Ok(set)
+        }
     }
 }
 
@@ -181,4 +527,243 @@ mod tests {
         assert_eq!(a.get(hello), None);
         assert_eq!(a.get(bye), Some(&"Bye!"));
     }
+
+    #[test]
+    fn test_key_display() {
+        let mut a = KeyedSet::new();
+        let first = a.insert(1);
+        let second = a.insert(2);
+
+        assert_eq!(first.to_string(), "#0");
+        assert_eq!(second.to_string(), "#1");
+    }
+
+    #[test]
+    fn test_stale_key() {
+        let mut a = KeyedSet::new();
+        let old = a.insert("Old");
+        a.remove(old);
+        let new = a.insert("New");
+
+        //  the slot is reused with a bumped generation
+        assert_eq!(new.index(), old.index());
+        assert_ne!(new, old);
+        assert_eq!(a.get(old), None);
+        assert_eq!(a.get_mut(old), None);
+        assert_eq!(a.remove(old), None);
+        assert_eq!(a.get(new), Some(&"New"));
+        //  keys print by their slot alone
+        assert_eq!(new.to_string(), "#0");
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut a = KeyedSet::new();
+        let hello = a.insert("Hello!");
+        let bye = a.insert("Bye!");
+        a.clear();
+
+        assert!(a.is_empty());
+        assert_eq!(a.get(hello), None);
+        assert_eq!(a.get(bye), None);
+
+        let again = a.insert("Hello again!");
+        assert_ne!(again, hello);
+        assert_ne!(again, bye);
+        assert_eq!(a.len(), 1);
+        assert_eq!(a.get(again), Some(&"Hello again!"));
+        assert_eq!(a.get(hello), None);
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut a = KeyedSet::new();
+        let keys: Vec<_> = (0..10).map(|i| a.insert(i)).collect();
+
+        let mut dropped = vec![];
+        a.retain(|key, &value| {
+            let keep = value % 2 == 0;
+            if !keep { dropped.push(key); }
+            keep
+        });
+        dropped.sort();
+
+        assert_eq!(a.len(), 5);
+        assert_eq!(dropped, keys.iter().cloned().filter(|key| key.index() % 2 == 1).collect::<Vec<_>>());
+        for (i, &key) in keys.iter().enumerate() {
+            assert_eq!(a.get(key), if i % 2 == 0 { Some(&i) } else { None });
+        }
+    }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        let mut a = KeyedSet::new();
+        let hello = a.insert("Hello!");
+        assert_eq!(*a.get_or_insert_with(hello, || "Other"), "Hello!");
+
+        //  an explicit key that was never handed out
+        let far = Key::new(3, 0);
+        assert_eq!(*a.get_or_insert_with(far, || "Far"), "Far");
+        assert_eq!(a.get(far), Some(&"Far"));
+        assert_eq!(a.len(), 2);
+
+        //  the skipped slots are handed out and never alias `far`
+        let b = a.insert("B");
+        let c = a.insert("C");
+        let d = a.insert("D");
+        let mut indices = vec![b.index(), c.index(), d.index()];
+        indices.sort();
+        assert_eq!(indices, vec![1, 2, 4]);
+        assert_eq!(a.get(far), Some(&"Far"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get_or_insert_with_stale_key() {
+        let mut a = KeyedSet::new();
+        let old = a.insert("Old");
+        a.remove(old);
+        a.get_or_insert_with(old, || "Stale");
+    }
+
+    #[test]
+    #[should_panic(expected = "is taken by")]
+    fn test_get_or_insert_with_taken_slot() {
+        let mut a = KeyedSet::new();
+        let live = a.insert("Live");
+        a.get_or_insert_with(Key::new(live.index(), 5), || "Newer");
+    }
+
+    #[test]
+    fn test_get_pair_mut() {
+        let mut a = KeyedSet::new();
+        let one = a.insert(1);
+        let two = a.insert(2);
+
+        let (x, y) = a.get_pair_mut(one, two).unwrap();
+        *x += 10;
+        *y += 20;
+        assert_eq!(a.get(one), Some(&11));
+        assert_eq!(a.get(two), Some(&22));
+
+        assert_eq!(a.get_pair_mut(one, one), None);
+        a.remove(two);
+        assert_eq!(a.get_pair_mut(one, two), None);
+    }
+
+    #[test]
+    fn test_keys_and_values() {
+        let mut a = KeyedSet::new();
+        let one = a.insert(1);
+        let two = a.insert(2);
+
+        let mut keys: Vec<_> = a.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec![one, two]);
+
+        for value in a.values_mut() { *value *= 10; }
+        let mut values: Vec<_> = a.values().cloned().collect();
+        values.sort();
+        assert_eq!(values, vec![10, 20]);
+    }
+
+    #[test]
+    fn test_insertion_order() {
+        for _ in 0..10 {
+            let mut a = KeyedSet::new();
+            let mut inserted = vec![];
+            for i in 0..20 { inserted.push((a.insert(i), i)); }
+            //  reused slots still go last
+            let (removed, _) = inserted.remove(3);
+            a.remove(removed);
+            inserted.push((a.insert(100), 100));
+
+            let iterated: Vec<_> = a.iter().map(|(&key, &value)| (key, value)).collect();
+            assert_eq!(iterated, inserted);
+            let iterated: Vec<_> = a.into_iter().collect();
+            assert_eq!(iterated, inserted);
+        }
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut a = KeyedSet::new();
+        let inserted: Vec<_> = (0..5).map(|i| (a.insert(i), i)).collect();
+
+        let drained: Vec<_> = a.drain().collect();
+        assert_eq!(a.len(), 0);
+        assert_eq!(drained, inserted);
+
+        //  the set is reusable and keys stay unique
+        let again = a.insert(5);
+        assert!(inserted.iter().all(|&(key, _)| key != again));
+        assert_eq!(a.get(again), Some(&5));
+    }
+
+    #[test]
+    fn test_index() {
+        let mut a = KeyedSet::new();
+        let hello = a.insert("Hello!");
+        assert_eq!(a[hello], "Hello!");
+        a[hello] = "Hi!";
+        assert_eq!(a[hello], "Hi!");
+    }
+
+    #[test]
+    #[should_panic(expected = "key #0 is not in the set")]
+    fn test_index_removed_key() {
+        let mut a = KeyedSet::new();
+        let hello = a.insert("Hello!");
+        a.remove(hello);
+        let _ = a[hello];
+    }
+
+    #[test]
+    fn test_from_iter_and_extend() {
+        let mut a: KeyedSet<_> = (0..3).collect();
+        a.extend(3..5);
+
+        assert_eq!(a.len(), 5);
+        assert_eq!(a.values().cloned().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut a = KeyedSet::with_capacity(100);
+        assert!(a.capacity() >= 100);
+
+        for i in 0..100 { a.insert(i); }
+        a.reserve(50);
+        assert!(a.capacity() >= 150);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut a = KeyedSet::new();
+        let one = a.insert(1);
+        let two = a.insert(2);
+        let three = a.insert(3);
+        a.remove(two);
+
+        let json = serde_json::to_string(&a).unwrap();
+        let mut b: KeyedSet<i32> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(b.len(), 2);
+        assert_eq!(b.get(one), Some(&1));
+        assert_eq!(b.get(two), None);
+        assert_eq!(b.get(three), Some(&3));
+
+        //  the freed slot keeps its generation, so no key aliases
+        let four = b.insert(4);
+        assert_ne!(four, two);
+        assert_eq!(four, a.insert(4));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_small_next() {
+        let json = r#"{"entries":[[[3,0],1]],"generations":[0],"next":1}"#;
+        assert!(serde_json::from_str::<KeyedSet<i32>>(json).is_err());
+    }
 }
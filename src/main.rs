@@ -1,131 +1,336 @@
+//  the modules are a library for simulations that this binary is one
+//  user of, so parts of their API are only used by tests
+#![allow(dead_code)]
+
 mod keyed_set;
+#[cfg(feature = "render")]
 mod window;
 mod physics;
 mod simulation;
+mod genome;
+mod stats;
+mod history;
+#[cfg(feature = "scent")]
+mod scent;
+mod replay;
+mod spawner;
+mod steering;
 mod math;
+mod args;
 
 use std::{
-    time,
+    env,
     io,
     fs,
     path,
+    process,
+};
+#[cfg(feature = "render")]
+use std::{
+    time,
     collections::HashMap,
 };
 
-use rand::{random, seq::SliceRandom};
+use rand::{Rng, seq::SliceRandom};
 
 use raylib::prelude::*;
 
 use crate::{
-    window::prelude::*,
     simulation::prelude::*,
+    genome::prelude::*,
+    spawner::prelude::*,
+    args::Args,
 };
+#[cfg(feature = "render")]
+use crate::window::prelude::*;
+
+fn random_vector2<R: Rng>(rng: &mut R) -> Vector2 { Vector2::new(rng.gen(), rng.gen()) }
+fn random_color<R: Rng>(rng: &mut R) -> Color { Color::new(rng.gen(), rng.gen(), rng.gen(), 255) }
 
-fn random_vector2() -> Vector2 { Vector2::new(random(), random()) }
-fn random_color() -> Color { Color::new(random(), random(), random(), 255) }
-
-fn add_random_blob(sim: &mut Simulation, names: &mut Vec<String>) -> keyed_set::Key<Blob> {
-    let key = sim.insert_blob(
-        random_vector2() * sim.size(),
-        20. * random::<f32>(),
-        random_color(),
-        120. * random::<f32>(),
-        5. * random::<f32>(),
-        180f32 * random::<f32>(),
-        170f32 * random::<f32>(),
-        random_color(),
-        random(),
-        random(),
-        25. * random::<f32>(),
-        random::<f32>(),
-        2. * random::<f32>(),
-        0.5 * random::<f32>(),
-        random::<f32>(),
-    );
-    let name = names.choose(&mut rand::thread_rng()).unwrap().to_string();
-    sim.get_blob_mut(key).unwrap().name = Some(name);
-    key
+/// Spawn a blob with random genes, and a random name if there are any.
+fn add_random_blob(sim: &mut Simulation, names: &[String]) -> keyed_set::Key<Blob> {
+    let size = sim.size();
+    let rng = sim.rng();
+    let mut blob = BlobBuilder::new();
+    if let Some(name) = names.choose(rng) {
+        blob = blob.name(name.clone());
+    }
+    let radius = 20. * rng.gen::<f32>();
+    let blob = blob
+        .pos(random_vector2(rng) * size)
+        .radius(radius)
+        .max_radius(radius * (1. + rng.gen::<f32>()))
+        .color(random_color(rng))
+        .speed(120. * rng.gen::<f32>())
+        .rotation_speed(5. * rng.gen::<f32>())
+        .pov(180f32 * rng.gen::<f32>())
+        .sight_depth(170f32 * rng.gen::<f32>())
+        .favorite_color(random_color(rng))
+        .color_attraction(rng.gen())
+        .color_repulsion(rng.gen())
+        .steering(if rng.gen() { Steering::ColorAverage } else { Steering::NearestFood })
+        .max_hunger(25. * rng.gen::<f32>())
+        .attack(rng.gen::<f32>())
+        .defence(2. * rng.gen::<f32>())
+        .hunger_reduction(0.5 * rng.gen::<f32>())
+        .hunger_division(rng.gen::<f32>())
+        .max_lifespan(60. + 120. * rng.gen::<f32>());
+    sim.spawn_blob(blob)
 }
 
-fn add_random_food(sim: &mut Simulation) -> keyed_set::Key<Food> {
-    sim.insert_food(random_vector2() * sim.size())
+/// The names blobs get when there is no names file.
+const BUILTIN_NAMES: &[&str] = &[
+    "Alice", "Bob", "Carol", "Dave", "Eve", "Frank", "Grace", "Heidi",
+    "Ivan", "Judy", "Mallory", "Niaj", "Olivia", "Peggy", "Rupert", "Sybil",
+    "Trent", "Victor", "Walter", "Yoni",
+];
+
+/// Read whitespace separated names from a file. Unless the file is
+/// `required`, return the builtin names if there is no such file.
+fn read_names<P: AsRef<path::Path> + ?Sized>(path: &P, required: bool) -> io::Result<Vec<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content.split_whitespace().map(|x| x.to_string()).collect()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound && !required => {
+            Ok(BUILTIN_NAMES.iter().map(|x| x.to_string()).collect())
+        },
+        Err(err) => Err(err),
+    }
 }
 
-fn read_names<P: AsRef<path::Path> + ?Sized>(path: &P) -> io::Result<Vec<String>> {
-    let content = fs::read_to_string(path)?;
-    Ok(content.split_whitespace().map(|x| x.to_string()).collect())
-}  
+/// The names from `--names`, which must exist, or from the default
+/// names file if there is one.
+fn names(args: &Args) -> io::Result<Vec<String>> {
+    match &args.names {
+        Some(path) => read_names(path, true),
+        None => read_names(NAMES_PATH, false),
+    }
+}
 
+#[cfg(feature = "render")]
 struct Selection {
     start_mouse_pos: Vector2,
     blobs: HashMap<keyed_set::Key<Blob>, Vector2>,
 }
 
-fn main() {
+const DEFAULT_WIDTH: u32 = 1300;
+const DEFAULT_HEIGHT: u32 = 680;
+const DEFAULT_BLOBS: usize = 10;
+const DEFAULT_FOODS: usize = 100;
+const NAMES_PATH: &str = "names.txt";
+
+/// The settings of the simulation, after the command line arguments.
+fn simulation_config(args: &Args) -> SimulationConfig {
+    let mut config = SimulationConfig {
+        size: Vector2::new(DEFAULT_WIDTH as f32, DEFAULT_HEIGHT as f32),
+        initial_blobs: DEFAULT_BLOBS,
+        initial_foods: DEFAULT_FOODS,
+        ..SimulationConfig::default()
+    };
+    args.apply(&mut config);
+    config
+}
+
+/// Run the simulation without a window, for a minute of simulated time.
+fn run_headless(args: &Args) -> io::Result<()> {
+    //  options
+    let timestep = 1. / 60.;
+    let steps = 60 * 60;
+    let food_spawn_strategy = FoodSpawnStrategy::FixedRate { per_second: 5. };
+    let config = simulation_config(args);
+    //  the blobs are added with names below
+    let start_blobs = config.initial_blobs;
+
+    //  allocate resources
+    let mut sim = Simulation::with_config(SimulationConfig { initial_blobs: 0, ..config });
+    sim.food_spawner = FoodSpawner::new(food_spawn_strategy);
+    let names = names(args)?;
+
+    //  initialize simulation
+    sim.reserve(start_blobs, 0);
+    for _ in 0..start_blobs {
+        add_random_blob(&mut sim, &names);
+    }
+
+    let mut births = 0;
+    let mut deaths = 0;
+    for step in 1..=steps {
+        sim.step(timestep);
+        for event in sim.take_events() {
+            match event {
+                SimulationEvent::Born { .. } => births += 1,
+                SimulationEvent::Died { .. } => deaths += 1,
+                _ => (),
+            }
+        }
+        //  report every 10 simulated seconds
+        if step % (60 * 10) == 0 {
+            let stats = sim.stats();
+            println!("{:.0}s: {} blobs ({} born, {} died), {} foods, mean speed {:.1}, mean attack {:.2}",
+                step as f32 * timestep, stats.blob_count, births, deaths, stats.food_count,
+                stats.speed.mean, stats.attack.mean);
+        }
+    }
+
+    let stats = sim.stats();
+    println!("Final: {} blobs ({} born, {} died), {} foods", stats.blob_count, births, deaths, stats.food_count);
+    println!("speed {:?}", stats.speed);
+    println!("pov {:?}", stats.pov);
+    println!("sight depth {:?}", stats.sight_depth);
+    println!("attack {:?}", stats.attack);
+    println!("defence {:?}", stats.defence);
+    Ok(())
+}
+
+#[cfg(feature = "render")]
+fn run_window(args: &Args) -> io::Result<()> {
     //  options
-    let food_add_delay = time::Duration::from_secs_f32(0.2);
+    let food_spawn_strategy = FoodSpawnStrategy::FixedRate { per_second: 5. };
     let blob_add_delay = time::Duration::from_secs_f32(0.5);
-    let start_blobs = 10;
-    let start_foods = 100;
+    let max_substep = 1. / 60.;
+    let config = simulation_config(args);
+    //  the blobs are added with names below
+    let start_blobs = config.initial_blobs;
+    let start_foods = config.initial_foods;
     let window_config = WindowConfig {
-        width: 1300,
-        height: 680,
+        width: config.size.x as u32,
+        height: config.size.y as u32,
         title: "Blobs",
+        target_fps: Some(60),
+        resizable: true,
     }; 
 
     //  allocate resources
     let mut window = Window::new(&window_config);
-    let mut sim = Simulation::new(Vector2::new(window.width() as f32, window.height() as f32));
-    let mut food_add_time = time::Instant::now(); 
+    let mut sim = Simulation::with_config(SimulationConfig { size: window.size(), initial_blobs: 0, ..config });
+    sim.food_spawner = FoodSpawner::new(food_spawn_strategy);
     let mut blob_add_time = time::Instant::now(); 
-    let mut names = read_names("names.txt").unwrap();
+    let names = names(args)?;
     
     //  initialize simulation
+    sim.reserve(start_blobs, 0);
     for _ in 0..start_blobs {
-        let blob_key = add_random_blob(&mut sim, &mut names);
-    }
-    //  initialize simulation
-    for _ in 0..start_foods {
-        add_random_food(&mut sim);
+        let blob_key = add_random_blob(&mut sim, &names);
     }
 
-    let mut last_frame_time = time::Instant::now();
     let mut selection: Option<Selection> = None;
-    window.draw_loop(|mut draw| {
-        //  record time and calculate delta
+    //  where a box selection started, and the blobs it selected
+    let mut selection_box: Option<Vector2> = None;
+    let mut group: Vec<keyed_set::Key<Blob>> = vec![];
+    let mut camera = Camera::new();
+    let mut show_hud = true;
+    let mut show_colliders = false;
+    let hud_config = HudConfig::default();
+    window.draw_loop(|draw, delta_time| {
         let frame_time = time::Instant::now();
-        let delta_time = (frame_time - last_frame_time).as_secs_f32();
-        last_frame_time = frame_time;
+        //  keep the simulation as large as the window
+        if draw.is_window_resized() {
+            let size = Vector2::new(draw.get_screen_width() as f32, draw.get_screen_height() as f32);
+            sim.set_size(size, false);
+        }
+
         //  draw and simulate
         draw.clear_background(Color::WHITE);
-        sim.draw(&mut draw);
-        sim.step(delta_time);
+        camera.update(draw);
+        {
+            let mut draw = camera.begin(draw);
+            sim.draw(&mut draw);
+            if show_colliders {
+                sim.physics.debug_draw(&mut draw, Color::RED);
+            }
+        }
+        sim.step_subdivided(delta_time, max_substep);
+        //  the events are not shown, so they must not pile up
+        sim.take_events();
 
         //  add blob
         if frame_time > blob_add_time {
             blob_add_time = frame_time + blob_add_delay;
-            let blob_key = add_random_blob(&mut sim, &mut names);
-        }
-        //  add food
-        if frame_time > food_add_time {
-            food_add_time = frame_time + food_add_delay;
-            add_random_food(&mut sim);
+            let blob_key = add_random_blob(&mut sim, &names);
         }
 
         if draw.is_key_down(KeyboardKey::KEY_SPACE) {
-            add_random_blob(&mut sim, &mut names);
+            add_random_blob(&mut sim, &names);
+        }
+
+        //  start over
+        if draw.is_key_pressed(KeyboardKey::KEY_R) {
+            sim.reset();
+            for _ in 0..start_blobs {
+                add_random_blob(&mut sim, &names);
+            }
+            for _ in 0..start_foods {
+                let pos = random_vector2(sim.rng()) * sim.size();
+                sim.insert_food(pos);
+            }
+        }
+
+        //  pause and change speed
+        if draw.is_key_pressed(KeyboardKey::KEY_P) {
+            sim.set_paused(!sim.paused());
+        }
+        if draw.is_key_pressed(KeyboardKey::KEY_EQUAL) || draw.is_key_pressed(KeyboardKey::KEY_KP_ADD) {
+            sim.set_time_scale(sim.time_scale() * 2.);
+        }
+        if draw.is_key_pressed(KeyboardKey::KEY_MINUS) || draw.is_key_pressed(KeyboardKey::KEY_KP_SUBTRACT) {
+            sim.set_time_scale(sim.time_scale() / 2.);
         }
 
-        if draw.is_mouse_button_down(MouseButton::MOUSE_LEFT_BUTTON) {
+        //  toggle the sight cones
+        if draw.is_key_pressed(KeyboardKey::KEY_V) {
+            sim.show_sight = !sim.show_sight;
+        }
+
+        //  toggle the physics colliders
+        if draw.is_key_pressed(KeyboardKey::KEY_C) {
+            show_colliders = !show_colliders;
+        }
+
+        //  toggle the HUD
+        if draw.is_key_pressed(KeyboardKey::KEY_H) {
+            show_hud = !show_hud;
+        }
+        if show_hud {
+            sim.draw_hud(draw, &hud_config);
+        }
+
+        //  reset the view
+        if draw.is_key_pressed(KeyboardKey::KEY_HOME) {
+            camera.reset();
+        }
+
+        let mouse_pos = camera.screen_to_world(draw.get_mouse_position());
+        let shift = draw.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || draw.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+        if let Some(box_start) = selection_box {
+            let min = Vector2::new(box_start.x.min(mouse_pos.x), box_start.y.min(mouse_pos.y));
+            let max = Vector2::new(box_start.x.max(mouse_pos.x), box_start.y.max(mouse_pos.y));
+            if draw.is_mouse_button_down(MouseButton::MOUSE_LEFT_BUTTON) {
+                let corner = camera.world_to_screen(min);
+                let size = (max - min) * camera.zoom;
+                draw.draw_rectangle_lines(corner.x as i32, corner.y as i32, size.x as i32, size.y as i32, Color::BLUE);
+            } else {
+                group = sim.select_rect(min, max).0;
+                selection_box = None;
+            }
+        } else if shift && draw.is_mouse_button_pressed(MouseButton::MOUSE_LEFT_BUTTON) {
+            selection_box = Some(mouse_pos);
+        } else if draw.is_mouse_button_down(MouseButton::MOUSE_LEFT_BUTTON) {
             if let Some(selection) = &mut selection {
                 for (&blob_key, start_pos) in &selection.blobs {
-                    sim.set_blob_pos(blob_key, *start_pos + draw.get_mouse_position() - selection.start_mouse_pos);
+                    sim.set_blob_pos(blob_key, *start_pos + mouse_pos - selection.start_mouse_pos);
                 }
             } else {
-                let (blobs, _) = sim.select(draw.get_mouse_position());
+                let (mut blobs, _) = sim.select(mouse_pos);
+                //  grabbing a blob of the group drags the whole group
+                if blobs.iter().any(|blob_key| group.contains(blob_key)) {
+                    blobs = group.clone();
+                } else {
+                    group.clear();
+                }
                 selection = Some(Selection {
-                    start_mouse_pos: draw.get_mouse_position(),
-                    blobs: blobs.iter().map(|&blob_key| (blob_key, sim.get_blob(blob_key).unwrap().pos())).collect(),
+                    start_mouse_pos: mouse_pos,
+                    blobs: blobs.iter()
+                        .filter_map(|&blob_key| Some((blob_key, sim.get_blob(blob_key)?.pos())))
+                        .collect(),
                 });
             }
         } else {
@@ -138,12 +343,96 @@ fn main() {
                 if let Some(blob) = sim.get_blob(blob_key) {
                     let font_size = 20;
                     draw.draw_text(
-                        &format!("Speed: {} Pov: {} Depth: {}", blob.speed, blob.pov, blob.sight_depth()), 
+                        &format!("Speed: {} Pov: {} Depth: {}", blob.genome().speed, blob.genome().pov, blob.sight_depth()), 
                         10, y, font_size, Color::BLACK
                     );
                     y += font_size;
                 }
             }
         }
+
+        if draw.is_key_pressed(KeyboardKey::KEY_Q) {
+            ControlFlow::Exit
+        } else if draw.is_key_pressed(KeyboardKey::KEY_F2) {
+            let timestamp = time::SystemTime::now().duration_since(time::UNIX_EPOCH).unwrap().as_secs();
+            ControlFlow::Screenshot(format!("screenshot-{}.png", timestamp).into())
+        } else {
+            ControlFlow::Continue
+        }
     });
-}
\ No newline at end of file
+    Ok(())
+}
+
+fn run(args: &Args) -> io::Result<()> {
+    #[cfg(feature = "render")]
+    {
+        if !args.headless {
+            return run_window(args);
+        }
+    }
+    run_headless(args)
+}
+
+fn main() {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, args::USAGE);
+            process::exit(2);
+        },
+    };
+    if args.help {
+        println!("{}", args::USAGE);
+        return;
+    }
+
+    if let Err(err) = run(&args) {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //  a path in the temporary directory, unique to the test
+    fn temp_path(name: &str) -> path::PathBuf {
+        env::temp_dir().join(format!("blobs-{}-{}", process::id(), name))
+    }
+
+    #[test]
+    fn test_missing_names_file_uses_builtin_names() {
+        let names = read_names(&temp_path("missing.txt"), false).unwrap();
+        assert_eq!(names.len(), BUILTIN_NAMES.len());
+        assert_eq!(names[0], BUILTIN_NAMES[0]);
+    }
+
+    #[test]
+    fn test_missing_names_argument_is_an_error() {
+        let args = Args { names: Some(temp_path("mistyped.txt")), ..Args::default() };
+        assert_eq!(names(&args).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_empty_names_file_leaves_blobs_unnamed() {
+        let path = temp_path("empty.txt");
+        fs::write(&path, "").unwrap();
+        let names = read_names(&path, true).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(names.is_empty());
+
+        let mut sim = Simulation::new_seeded(Vector2::new(100., 100.), 7);
+        let blob = add_random_blob(&mut sim, &names);
+        assert_eq!(sim.get_blob(blob).unwrap().name, None);
+    }
+
+    #[test]
+    fn test_names_file() {
+        let path = temp_path("names.txt");
+        fs::write(&path, "Ann  Ben\nCid\n").unwrap();
+        let names = read_names(&path, true).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(names, vec!["Ann", "Ben", "Cid"]);
+    }
+}
@@ -1,17 +1,67 @@
+use rand::{prelude::*, rngs::StdRng};
+
 use raylib::prelude::*;
 
 pub use raylib::prelude::Vector3;
 
+/// Returns the angle between the vectors in degrees, in [0, 180].
 pub fn unsigned_angle_vector2(a: Vector2, b: Vector2) -> f32 {
-    let mut angle = a.angle_to(b).to_degrees().abs();
-    if angle > 180. { angle -= 180. }
-    angle
+    //  the angle to rotate a by to reach b, in [0, 360)
+    let angle = (b.y.atan2(b.x) - a.y.atan2(a.x)).to_degrees().rem_euclid(360.);
+    if angle > 180. { 360. - angle } else { angle }
+}
+
+/// Returns the angle to rotate `a` by to point it like `b`, in
+/// degrees in (-180, 180]. It is positive from the x axis toward the
+/// y axis, which is clockwise on the screen, where y points down.
+pub fn signed_angle_vector2(a: Vector2, b: Vector2) -> f32 {
+    let cross = a.x * b.y - a.y * b.x;
+    let angle = cross.atan2(a.dot(b)).to_degrees();
+    if angle <= -180. { 180. } else { angle }
+}
+
+/// Returns `v` rotated by `degrees`, from the x axis toward the y axis
+/// like `signed_angle_vector2`.
+pub fn rotate(v: Vector2, degrees: f32) -> Vector2 {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    Vector2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+
+/// Returns `v` rotated by 90 degrees.
+pub fn perpendicular(v: Vector2) -> Vector2 {
+    Vector2::new(-v.y, v.x)
+}
+
+/// Returns `v` scaled down to a length of `max` if it is longer,
+/// or `v` itself otherwise.
+pub fn clamp_magnitude(v: Vector2, max: f32) -> Vector2 {
+    debug_assert!(max >= 0.);
+    let length_sqr = v.length_sqr();
+    if length_sqr > max * max { v * (max / length_sqr.sqrt()) } else { v }
+}
+
+/// Returns the part of `v` that points along `onto`, or zero if
+/// `onto` is zero.
+pub fn project(v: Vector2, onto: Vector2) -> Vector2 {
+    let length_sqr = onto.length_sqr();
+    if length_sqr == 0. { return Vector2::zero(); }
+    onto * (v.dot(onto) / length_sqr)
+}
+
+/// Returns `v` mirrored off a surface with the given normal, which
+/// must be normalized.
+pub fn reflect(v: Vector2, normal: Vector2) -> Vector2 {
+    v - normal * (2. * v.dot(normal))
 }
 
 pub fn slerp(start: Vector2, end: Vector2, time: f32) -> Vector2 {
     //  https://en.wikipedia.org/wiki/Slerp
     //  slerp(p0, p1, t) = sin((1-t)a) / sin a * p0 + sin ta / sin a * p1
 
+    //  without a direction there is nothing to turn through
+    if start.length_sqr() == 0. { return if end.length_sqr() == 0. { start } else { end.normalized() }; }
+    if end.length_sqr() == 0. { return start; }
+
     if (start - end).length_sqr() <= 0.01 { return start; }
 
     let p0 = start;
@@ -20,5 +70,265 @@ pub fn slerp(start: Vector2, end: Vector2, time: f32) -> Vector2 {
     let a = unsigned_angle_vector2(start, end).to_radians();
     let sa = a.sin();
 
+    //  opposite vectors have no single arc between them, so turn
+    //  counterclockwise through the half circle
+    if sa.abs() < 1e-3 {
+        return rotate(p0, (t * a).to_degrees()).normalized();
+    }
+
     (p0 * (((1. - t) * a).sin() / sa) + p1 * ((t * a).sin() / sa)).normalized()
-}
\ No newline at end of file
+}
+
+/// Returns `current` rotated toward `target` by at most
+/// `max_delta_degrees`, or the direction of `target` if it is closer
+/// than that. The result is normalized.
+pub fn move_towards(current: Vector2, target: Vector2, max_delta_degrees: f32) -> Vector2 {
+    if target.length_sqr() == 0. { return current; }
+    if current.length_sqr() == 0. { return target.normalized(); }
+
+    let angle = signed_angle_vector2(current, target);
+    if angle.abs() <= max_delta_degrees {
+        target.normalized()
+    } else {
+        rotate(current, max_delta_degrees.copysign(angle)).normalized()
+    }
+}
+
+/// Returns a sample of the standard normal distribution,
+/// using the Box-Muller transform.
+pub fn gaussian<R: Rng + ?Sized>(rng: &mut R) -> f32 {
+    //  u1 in (0,1] so the log is finite
+    let u1: f32 = 1. - rng.gen::<f32>();
+    let u2: f32 = rng.gen();
+    (-2. * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+}
+
+/// Returns a vector of length 1 pointing in a uniformly random direction.
+pub fn unit_vector2<R: Rng + ?Sized>(rng: &mut R) -> Vector2 {
+    let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+    Vector2::new(angle.cos(), angle.sin())
+}
+
+/// Returns one of the choices, each with a chance proportional to its
+/// weight, or `None` if no choice has a positive weight.
+pub fn weighted_choice<'a, T, R: Rng + ?Sized>(rng: &mut R, choices: &'a [(T, f32)]) -> Option<&'a T> {
+    let total: f32 = choices.iter().map(|&(_, weight)| weight.max(0.)).sum();
+    if total <= 0. { return None; }
+    let mut left = rng.gen::<f32>() * total;
+    for (choice, weight) in choices {
+        let weight = weight.max(0.);
+        if left < weight { return Some(choice); }
+        left -= weight;
+    }
+    //  rounding can leave a little over, which goes to the last choice
+    choices.iter().rev().find(|&&(_, weight)| weight > 0.).map(|(choice, _)| choice)
+}
+
+/// A random number generator with the distributions the simulation
+/// uses, which is seeded so runs can be repeated.
+///
+/// It is an `Rng` itself, so it can be passed to anything that takes one.
+#[derive(Debug, Clone)]
+pub struct Random {
+    rng: StdRng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self { Self { rng: StdRng::seed_from_u64(seed) } }
+
+    /// A generator that is different every run.
+    pub fn from_entropy() -> Self { Self { rng: StdRng::from_entropy() } }
+
+    pub fn unit_vector2(&mut self) -> Vector2 { unit_vector2(&mut self.rng) }
+
+    /// Returns a sample of the normal distribution with the given
+    /// mean and standard deviation.
+    pub fn gaussian(&mut self, mean: f32, std: f32) -> f32 { mean + std * gaussian(&mut self.rng) }
+
+    /// See `weighted_choice`.
+    pub fn weighted_choice<'a, T>(&mut self, choices: &'a [(T, f32)]) -> Option<&'a T> {
+        weighted_choice(&mut self.rng, choices)
+    }
+}
+
+impl RngCore for Random {
+    fn next_u32(&mut self) -> u32 { self.rng.next_u32() }
+
+    fn next_u64(&mut self) -> u64 { self.rng.next_u64() }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) { self.rng.fill_bytes(dest) }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> { self.rng.try_fill_bytes(dest) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_degrees(degrees: f32) -> Vector2 {
+        let radians = degrees.to_radians();
+        Vector2::new(radians.cos(), radians.sin())
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_unsigned_angle_folds_above_180() {
+        let a = Vector2::new(1., 0.);
+        assert_close(unsigned_angle_vector2(a, from_degrees(90.)), 90.);
+        assert_close(unsigned_angle_vector2(a, from_degrees(200.)), 160.);
+        assert_close(unsigned_angle_vector2(a, from_degrees(270.)), 90.);
+        assert_close(unsigned_angle_vector2(a, from_degrees(350.)), 10.);
+        assert_close(unsigned_angle_vector2(from_degrees(350.), a), 10.);
+    }
+
+    #[test]
+    fn test_signed_angle_direction() {
+        let a = Vector2::new(1., 0.);
+        assert_close(signed_angle_vector2(a, from_degrees(30.)), 30.);
+        assert_close(signed_angle_vector2(a, from_degrees(-30.)), -30.);
+        assert_close(signed_angle_vector2(from_degrees(30.), a), -30.);
+        assert_close(signed_angle_vector2(a, from_degrees(200.)), -160.);
+        assert_close(signed_angle_vector2(a, Vector2::new(-1., 0.)), 180.);
+        assert_close(signed_angle_vector2(a, Vector2::new(-1., -0.)), 180.);
+    }
+
+    #[test]
+    fn test_rotate() {
+        let v = rotate(Vector2::new(1., 0.), 90.);
+        assert_close(v.x, 0.);
+        assert_close(v.y, 1.);
+        let v = rotate(Vector2::new(2., 0.), -45.);
+        assert_close(v.length(), 2.);
+        assert_close(signed_angle_vector2(Vector2::new(1., 0.), v), -45.);
+    }
+
+    #[test]
+    fn test_perpendicular() {
+        for &v in &[Vector2::new(1., 0.), Vector2::new(3., -4.), Vector2::new(-0.5, 2.)] {
+            let p = perpendicular(v);
+            assert_eq!(v.dot(p), 0.);
+            assert_eq!(p.length(), v.length());
+            assert_close(signed_angle_vector2(v, p), 90.);
+        }
+    }
+
+    #[test]
+    fn test_move_towards_snaps_within_range() {
+        let target = Vector2::new(0., 2.);
+        assert_eq!(move_towards(from_degrees(80.), target, 15.), Vector2::new(0., 1.));
+    }
+
+    #[test]
+    fn test_move_towards_does_not_overshoot() {
+        let current = Vector2::new(1., 0.);
+        let v = move_towards(current, from_degrees(90.), 30.);
+        assert_close(signed_angle_vector2(current, v), 30.);
+        let v = move_towards(current, from_degrees(-90.), 30.);
+        assert_close(signed_angle_vector2(current, v), -30.);
+        assert_close(v.length(), 1.);
+    }
+
+    #[test]
+    fn test_gaussian_mean_and_std() {
+        let mut random = Random::new(7);
+        let samples: Vec<f32> = (0..10000).map(|_| random.gaussian(5., 2.)).collect();
+        let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+        let variance = samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / samples.len() as f32;
+        assert!((mean - 5.).abs() < 0.1, "{}", mean);
+        assert!((variance.sqrt() - 2.).abs() < 0.1, "{}", variance.sqrt());
+    }
+
+    #[test]
+    fn test_unit_vector2() {
+        let mut random = Random::new(7);
+        for _ in 0..100 {
+            assert_close(random.unit_vector2().length(), 1.);
+        }
+    }
+
+    #[test]
+    fn test_weighted_choice() {
+        let mut random = Random::new(7);
+        let choices = [("never", 0.), ("rare", 1.), ("common", 3.)];
+        let mut common = 0;
+        for _ in 0..1000 {
+            match random.weighted_choice(&choices) {
+                Some(&"common") => common += 1,
+                Some(&"rare") => (),
+                other => panic!("{:?}", other),
+            }
+        }
+        assert!((700..800).contains(&common), "{}", common);
+        assert_eq!(random.weighted_choice(&[("never", 0.)]), None);
+        assert_eq!(random.weighted_choice::<&str>(&[]), None);
+    }
+
+    #[test]
+    fn test_seeded_random_repeats() {
+        let mut a = Random::new(7);
+        let mut b = Random::new(7);
+        assert_eq!(a.gen::<u64>(), b.gen::<u64>());
+        assert_eq!(a.unit_vector2(), b.unit_vector2());
+    }
+
+    #[test]
+    fn test_clamp_magnitude() {
+        let short = Vector2::new(1., 2.);
+        assert_eq!(clamp_magnitude(short, 5.), short);
+        let long = Vector2::new(30., 40.);
+        let clamped = clamp_magnitude(long, 5.);
+        assert_close(clamped.length(), 5.);
+        assert_close(clamped.x, 3.);
+        assert_close(clamped.y, 4.);
+    }
+
+    #[test]
+    fn test_project() {
+        let v = Vector2::new(3., -4.);
+        assert_eq!(project(v, Vector2::new(2., 0.)), Vector2::new(3., 0.));
+        assert_eq!(project(v, Vector2::new(0., -1.)), Vector2::new(0., -4.));
+        assert_eq!(project(v, Vector2::zero()), Vector2::zero());
+        //  the rest of v is perpendicular to the axis
+        let onto = Vector2::new(1., 1.);
+        assert_close((v - project(v, onto)).dot(onto), 0.);
+    }
+
+    #[test]
+    fn test_reflect() {
+        let v = Vector2::new(3., -4.);
+        //  off a vertical wall
+        assert_eq!(reflect(v, Vector2::new(-1., 0.)), Vector2::new(-3., -4.));
+        //  off a horizontal wall
+        assert_eq!(reflect(v, Vector2::new(0., 1.)), Vector2::new(3., 4.));
+        //  along the wall
+        assert_eq!(reflect(Vector2::new(0., 2.), Vector2::new(1., 0.)), Vector2::new(0., 2.));
+    }
+
+    #[test]
+    fn test_slerp_opposite_vectors() {
+        let start = Vector2::new(1., 0.);
+        let end = Vector2::new(-1., 0.);
+        for &t in &[0., 0.25, 0.5, 1.] {
+            let v = slerp(start, end, t);
+            assert!(v.x.is_finite() && v.y.is_finite(), "{:?}", v);
+            assert_close(v.length(), 1.);
+        }
+        assert_close(unsigned_angle_vector2(slerp(start, end, 0.5), start), 90.);
+    }
+
+    #[test]
+    fn test_slerp_zero_vectors() {
+        let v = Vector2::new(0., 1.);
+        assert_eq!(slerp(Vector2::zero(), v, 0.5), v);
+        assert_eq!(slerp(v, Vector2::zero(), 0.5), v);
+        assert_eq!(slerp(Vector2::zero(), Vector2::zero(), 0.5), Vector2::zero());
+    }
+
+    #[test]
+    fn test_unsigned_angle_ignores_length() {
+        assert_close(unsigned_angle_vector2(Vector2::new(3., 0.), Vector2::new(0., -0.5)), 90.);
+    }
+}
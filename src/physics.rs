@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap, HashSet},
+    ops::{BitOr, BitOrAssign},
+};
 
 use raylib::prelude::*;
 
@@ -6,9 +10,11 @@ use crate::keyed_set::prelude::*;
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
 pub struct Layer(u32);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerMask(u32);
 
 impl Layer {
@@ -40,53 +46,362 @@ impl LayerMask {
     }
 }
 
+impl From<Layer> for LayerMask {
+    fn from(Layer(bits): Layer) -> Self { Self(bits) }
+}
+
+impl BitOr<Layer> for Layer {
+    type Output = LayerMask;
+
+    fn bitor(self, other: Layer) -> LayerMask { LayerMask::from(self) | other }
+}
+
+impl BitOr<LayerMask> for Layer {
+    type Output = LayerMask;
+
+    fn bitor(self, other: LayerMask) -> LayerMask { other | self }
+}
+
+impl BitOr<Layer> for LayerMask {
+    type Output = LayerMask;
+
+    fn bitor(self, other: Layer) -> LayerMask { self | LayerMask::from(other) }
+}
+
+impl BitOr<LayerMask> for LayerMask {
+    type Output = LayerMask;
+
+    fn bitor(self, other: LayerMask) -> LayerMask { Self(self.0 | other.0) }
+}
+
+impl BitOrAssign<Layer> for LayerMask {
+    fn bitor_assign(&mut self, other: Layer) { self.add(other) }
+}
+
+impl BitOrAssign<LayerMask> for LayerMask {
+    fn bitor_assign(&mut self, other: LayerMask) { self.0 |= other.0 }
+}
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
 pub struct Circle {
     pub center: Vector2,
     pub radius: f32,
     pub layer: Layer,
+    /// Moves the center on `World::integrate`.
+    pub velocity: Vector2,
+    /// Whether the circle stays in place, like a food. Two static
+    /// circles are never checked against each other.
+    pub is_static: bool,
+    /// Whether the circle is a sensor, like a blob's sight. Triggers
+    /// only report what they overlap through `World::triggers`, and
+    /// are never part of `World::collisions`.
+    pub is_trigger: bool,
+}
+
+/// An axis-aligned box, for colliders like walls and patches.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct Aabb {
+    pub min: Vector2,
+    pub max: Vector2,
+    pub layer: Layer,
 }
 
 pub type CircleCollisions = HashMap<Key<Circle>, Vec<Key<Circle>>>;
 
+/// Where a ray first hit a circle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub key: Key<Circle>,
+    pub point: Vector2,
+    /// The distance along the ray to the hit point.
+    pub distance: f32,
+}
+
+/// How a circle overlaps another circle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    pub other: Key<Circle>,
+    /// Points from the other circle toward this one. Circles with the
+    /// same center are separated along the x axis, the circle with the
+    /// smaller key toward +x.
+    pub normal: Vector2,
+    /// How far the circles overlap along the normal.
+    pub depth: f32,
+}
+
+pub type CircleContacts = HashMap<Key<Circle>, Vec<Contact>>;
+
+/// The boxes touched by every circle touching any box.
+pub type AabbCollisions = HashMap<Key<Circle>, Vec<Key<Aabb>>>;
+
 pub type CollisionMatrix = HashMap<Layer, LayerMask>;
 
+/// Remembers the collisions of the last frame, to tell which started
+/// or stopped this frame.
+///
+/// Pairs are directed like `CircleCollisions`: `(a, b)` means `a`
+/// collides with `b`.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollisionTracker {
+    previous: BTreeSet<(Key<Circle>, Key<Circle>)>,
+    current: BTreeSet<(Key<Circle>, Key<Circle>)>,
+}
+
+impl CollisionTracker {
+    pub fn new() -> Self { Self::default() }
+
+    /// Advance to the next frame's collisions.
+    pub fn update(&mut self, collisions: &CircleCollisions) {
+        self.previous = std::mem::take(&mut self.current);
+        for (&key, collided) in collisions {
+            for &other in collided {
+                self.current.insert((key, other));
+            }
+        }
+    }
+
+    /// The pairs colliding this frame but not the last.
+    pub fn entered(&self) -> impl Iterator<Item = (Key<Circle>, Key<Circle>)> + '_ {
+        self.current.difference(&self.previous).cloned()
+    }
+
+    /// The pairs colliding the last frame but not this one.
+    pub fn exited(&self) -> impl Iterator<Item = (Key<Circle>, Key<Circle>)> + '_ {
+        self.previous.difference(&self.current).cloned()
+    }
+}
+
+/// The algorithm used to find candidate pairs for collision checks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub enum Broadphase {
+    /// Sort the circles by x and only compare circles that overlap on x.
+    SweepAndPrune,
+    /// Bucket the circles into square cells and only compare circles
+    /// that share a cell. Without a cell size, the cells are as wide as
+    /// the largest circle.
+    Grid { cell_size: Option<f32> },
+}
+
+impl Default for Broadphase {
+    fn default() -> Self { Self::Grid { cell_size: None } }
+}
+
+/// An axis that sweep and prune sorts along.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    X,
+    Y,
+}
+
+impl Default for Axis {
+    fn default() -> Self { Self::X }
+}
+
+impl Axis {
+    fn of(self, v: Vector2) -> f32 {
+        match self {
+            Axis::X => v.x,
+            Axis::Y => v.y,
+        }
+    }
+}
+
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
 pub struct World {
     pub circles: KeyedSet<Circle>,    
+    pub aabbs: KeyedSet<Aabb>,
     collision_matrix: CollisionMatrix,
+    pub broadphase: Broadphase,
+    /// The area circles are expected to stay in, if any.
+    pub bounds: Option<Rectangle>,
+    //  the axis of the last sweep, and the circles by their lower edge
+    //  on it, kept between frames since they are usually still nearly sorted
+    #[cfg_attr(feature = "save", serde(skip))]
+    sorted: RefCell<(Axis, Vec<Key<Circle>>)>,
 }
 
 
 impl Circle {
+    /// A circle that does not move until it is given a velocity.
+    pub fn new(center: Vector2, radius: f32, layer: Layer) -> Self {
+        Self { center, radius, layer, velocity: Vector2::zero(), is_static: false, is_trigger: false }
+    }
+
+    /// A circle that stays in place.
+    pub fn new_static(center: Vector2, radius: f32, layer: Layer) -> Self {
+        Self { is_static: true, ..Self::new(center, radius, layer) }
+    }
+
+    /// A sensor circle, see `is_trigger`.
+    pub fn new_trigger(center: Vector2, radius: f32, layer: Layer) -> Self {
+        Self { is_trigger: true, ..Self::new(center, radius, layer) }
+    }
+
+    /// Whether the circle can be checked against `other` for collisions.
+    fn can_collide(&self, other: &Self) -> bool {
+        !(self.is_static && other.is_static)
+    }
+
     pub fn intersects_x_axis(&self, other: &Self) -> bool {
-        (other.center.x - self.center.x).abs() <= self.radius + other.radius
+        self.intersects_axis(other, Axis::X)
+    }
+
+    fn intersects_axis(&self, other: &Self, axis: Axis) -> bool {
+        (axis.of(other.center) - axis.of(self.center)).abs() <= self.radius + other.radius
     }
 
     pub fn intersects(&self, other: &Self) -> bool {
         (other.center - self.center).length_sqr() <= (self.radius + other.radius) * (self.radius + other.radius)
     }
+
+    /// Whether `p` is inside the circle. The boundary is inside.
+    pub fn contains_point(&self, p: Vector2) -> bool {
+        (p - self.center).length_sqr() <= self.radius * self.radius
+    }
+
+    pub fn area(&self) -> f32 {
+        std::f32::consts::PI * self.radius * self.radius
+    }
+
+    pub fn circumference(&self) -> f32 {
+        2. * std::f32::consts::PI * self.radius
+    }
+
+    /// The distance along a ray to where it enters the circle.
+    ///
+    /// `dir` must be normalized. Returns 0 for rays that start inside
+    /// the circle, and `None` for rays that miss it.
+    pub fn ray_distance(&self, origin: Vector2, dir: Vector2) -> Option<f32> {
+        //  solve |origin + t * dir - center|^2 = radius^2 for t
+        let m = origin - self.center;
+        let b = m.dot(dir);
+        let c = m.length_sqr() - self.radius * self.radius;
+        //  starts outside and points away
+        if c > 0. && b > 0. { return None; }
+        let discriminant = b * b - c;
+        if discriminant < 0. { return None; }
+        Some(f32::max(-b - discriminant.sqrt(), 0.))
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        (aabb.closest_point(self.center) - self.center).length_sqr() <= self.radius * self.radius
+    }
+}
+
+impl Aabb {
+    pub fn width(&self) -> f32 { self.max.x - self.min.x }
+
+    pub fn height(&self) -> f32 { self.max.y - self.min.y }
+
+    /// The point in the box closest to `point`.
+    pub fn closest_point(&self, point: Vector2) -> Vector2 {
+        Vector2::new(
+            point.x.max(self.min.x).min(self.max.x),
+            point.y.max(self.min.y).min(self.max.y),
+        )
+    }
+
+    pub fn intersects_x_axis(&self, circle: &Circle) -> bool {
+        circle.center.x + circle.radius >= self.min.x && circle.center.x - circle.radius <= self.max.x
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x
+        && self.min.y <= other.max.y && other.min.y <= self.max.y
+    }
 }
 
 impl World {
     pub fn new(collision_matrix: CollisionMatrix) -> Self {
-        Self { circles: KeyedSet::new(), collision_matrix }
+        Self::with_broadphase(collision_matrix, Broadphase::default())
+    }
+
+    pub fn with_broadphase(collision_matrix: CollisionMatrix, broadphase: Broadphase) -> Self {
+        Self {
+            circles: KeyedSet::new(),
+            aabbs: KeyedSet::new(),
+            collision_matrix,
+            broadphase,
+            bounds: None,
+            sorted: RefCell::default(),
+        }
+    }
+
+    /// Move every circle by its velocity over `dt` seconds.
+    pub fn integrate(&mut self, dt: f32) {
+        for circle in self.circles.values_mut() {
+            if circle.velocity != Vector2::zero() {
+                circle.center += circle.velocity * dt;
+            }
+        }
+    }
+
+    /// Outline every circle and box in the world, whatever it belongs
+    /// to. Each layer gets its own hue, turned from `color`'s.
+    #[cfg(feature = "render")]
+    pub fn debug_draw<D: RaylibDraw>(&self, draw: &mut D, color: Color) {
+        let hsv = color.color_to_hsv();
+        let layer_color = |Layer(bits): Layer| {
+            //  8 layers around the color wheel
+            let hue = (hsv.x + 45. * bits.trailing_zeros() as f32).rem_euclid(360.);
+            Color::color_from_hsv(hue, hsv.y, hsv.z)
+        };
+        for circle in self.circles.values() {
+            draw.draw_circle_lines(circle.center.x as i32, circle.center.y as i32, circle.radius, layer_color(circle.layer));
+        }
+        for aabb in self.aabbs.values() {
+            let size = aabb.max - aabb.min;
+            draw.draw_rectangle_lines(aabb.min.x as i32, aabb.min.y as i32, size.x as i32, size.y as i32, layer_color(aabb.layer));
+        }
+    }
+
+    /// Draws nothing, since headless builds have nothing to draw on.
+    #[cfg(not(feature = "render"))]
+    pub fn debug_draw<D: RaylibDraw>(&self, _draw: &mut D, _color: Color) {}
+
+    /// Find every circle whose center is outside the bounds.
+    ///
+    /// Without bounds, nothing is out of bounds.
+    pub fn out_of_bounds(&self) -> Vec<Key<Circle>> {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return vec![],
+        };
+        self.circles.iter()
+            .filter(|(_, circle)| circle.center.x < bounds.x || circle.center.x > bounds.x + bounds.width
+                || circle.center.y < bounds.y || circle.center.y > bounds.y + bounds.height)
+            .map(|(&key, _)| key)
+            .collect()
     }
 
-    fn layers_collide(collision_matrix: &CollisionMatrix, left: &Circle, right: &Circle) -> bool {
-        match collision_matrix.get(&left.layer) {
+    /// The layers that `layer` collides with.
+    pub fn layer_mask(&self, layer: Layer) -> LayerMask {
+        self.collision_matrix.get(&layer).cloned().unwrap_or_else(LayerMask::full)
+    }
+
+    fn layers_collide(collision_matrix: &CollisionMatrix, left: Layer, right: Layer) -> bool {
+        match collision_matrix.get(&left) {
             None => true,
-            Some(layer_mask) => layer_mask.contains(&right.layer),
+            Some(layer_mask) => layer_mask.contains(&right),
         }
     }
 
+    //  checks every pair, to test the broadphases against
+    #[cfg(test)]
     fn collisions_naive<'a>(collision_matrix: &CollisionMatrix, circles: &Vec<(Key<Circle>, &'a Circle)>) -> CircleCollisions {
         let mut ret = CircleCollisions::new();
         for &(key, circle) in circles {
             let mut collided = vec![];
             for &(other_key, other_circle) in circles {
                 if other_key != key 
+                && !circle.is_trigger && !other_circle.is_trigger
+                && circle.can_collide(other_circle)
                 && circle.intersects(other_circle)
-                && Self::layers_collide(collision_matrix, circle, other_circle) {
+                && Self::layers_collide(collision_matrix, circle.layer, other_circle.layer) {
                     collided.push(other_key);
                 }
             }
@@ -97,56 +412,785 @@ impl World {
         ret    
     }
 
+    fn candidates(&self) -> Vec<(Key<Circle>, Key<Circle>)> {
+        match self.broadphase {
+            Broadphase::SweepAndPrune => self.candidates_sweep_and_prune(),
+            Broadphase::Grid { cell_size } => self.candidates_grid(cell_size),
+        }
+    }
+
+    /// Find what every solid circle collides with. Triggers are left out.
     pub fn collisions(&self) -> CircleCollisions {
-        //  use the sweep and prune algorithm
+        self.collisions_and_triggers().0
+    }
 
-        //  edge case - no circles
-        if self.circles.len() == 0 { return CircleCollisions::new() }
+    /// Find the solid circles every trigger overlaps, on the layers
+    /// its layer collides with. Triggers do not sense each other.
+    pub fn triggers(&self) -> CircleCollisions {
+        self.collisions_and_triggers().1
+    }
 
-        //  sort by x axis
-        let mut circles: Vec<(Key<Circle>, &Circle)> = self.circles
-            .iter()
-            .map(|tuple| (*tuple.0, tuple.1))
+    /// Both `collisions` and `triggers`, from a single broadphase.
+    pub fn collisions_and_triggers(&self) -> (CircleCollisions, CircleCollisions) {
+        //  check the candidate pairs in both directions
+        let mut collisions = CircleCollisions::new();
+        let mut triggers = CircleCollisions::new();
+        for (key, other_key) in self.candidates() {
+            let circle = &self.circles[key];
+            let other_circle = &self.circles[other_key];
+            if circle.is_trigger && other_circle.is_trigger { continue; }
+            if !circle.intersects(other_circle) { continue; }
+            let ret = if circle.is_trigger || other_circle.is_trigger { &mut triggers } else { &mut collisions };
+            if !other_circle.is_trigger && Self::layers_collide(&self.collision_matrix, circle.layer, other_circle.layer) {
+                ret.entry(key).or_default().push(other_key);
+            }
+            if !circle.is_trigger && Self::layers_collide(&self.collision_matrix, other_circle.layer, circle.layer) {
+                ret.entry(other_key).or_default().push(key);
+            }
+        }
+
+        //  list the collided circles by key, so the result is deterministic
+        for collided in collisions.values_mut().chain(triggers.values_mut()) {
+            collided.sort();
+        }
+        (collisions, triggers)
+    }
+
+    /// Every pair of colliding circles once, with the lower key first.
+    ///
+    /// A pair collides if either circle's layer collides with the other's.
+    /// Triggers are left out.
+    pub fn collision_pairs(&self) -> impl Iterator<Item = (Key<Circle>, Key<Circle>)> {
+        let mut ret: Vec<_> = self.candidates().into_iter()
+            .filter(|&(key, other_key)| {
+                let circle = &self.circles[key];
+                let other_circle = &self.circles[other_key];
+                !circle.is_trigger && !other_circle.is_trigger
+                && circle.intersects(other_circle)
+                && (Self::layers_collide(&self.collision_matrix, circle.layer, other_circle.layer)
+                    || Self::layers_collide(&self.collision_matrix, other_circle.layer, circle.layer))
+            })
+            .map(|(key, other_key)| (key.min(other_key), key.max(other_key)))
             .collect();
-        //  this line will not work because the sort-key is a vector
-        //circles.sort_by_key(|circle| circle.center.x);
-        circles.sort_by(|a, b| a.1.center.x.partial_cmp(&b.1.center.x).unwrap());
-
-        //  check for x-axis intersection between neighbors
-        let mut x_axis_collisions = vec![];
-        let mut active_interval = vec![circles[0]]; //   edge case where no 0th element is handled earlier
-        for (key, circle) in circles.into_iter().skip(1) {
-            if active_interval.iter().any(|other| other.1.intersects_x_axis(circle)) {
-                active_interval.push((key, circle));
+        ret.sort();
+        ret.into_iter()
+    }
+
+    /// Find every circle on the `mask` layers that contains `point`.
+    pub fn query_point(&self, point: Vector2, mask: LayerMask) -> Vec<Key<Circle>> {
+        keys(self.query_point_circles(point, mask))
+    }
+
+    /// Like `query_point`, with the circles.
+    pub fn query_point_circles(&self, point: Vector2, mask: LayerMask) -> Vec<(Key<Circle>, &Circle)> {
+        self.circles.iter()
+            .filter(|(_, circle)| mask.contains(&circle.layer) && circle.contains_point(point))
+            .map(|(&key, circle)| (key, circle))
+            .collect()
+    }
+
+    /// Find every circle on the `mask` layers whose center is in the box.
+    pub fn query_aabb(&self, aabb: &Aabb, mask: LayerMask) -> Vec<Key<Circle>> {
+        self.circles.iter()
+            .filter(|(_, circle)| mask.contains(&circle.layer) && aabb.closest_point(circle.center) == circle.center)
+            .map(|(&key, _)| key)
+            .collect()
+    }
+
+    /// Find the circle on the `mask` layers whose center is nearest to `from`.
+    ///
+    /// This scans every circle.
+    pub fn nearest(&self, from: Vector2, mask: LayerMask) -> Option<Key<Circle>> {
+        self.nearest_circle(from, mask).map(|(key, _)| key)
+    }
+
+    /// Like `nearest`, with the circle.
+    pub fn nearest_circle(&self, from: Vector2, mask: LayerMask) -> Option<(Key<Circle>, &Circle)> {
+        self.circles.iter()
+            .filter(|(_, circle)| mask.contains(&circle.layer))
+            .map(|(&key, circle)| (key, circle, (circle.center - from).length_sqr()))
+            .fold(None, |nearest: Option<(Key<Circle>, &Circle, f32)>, (key, circle, distance)| match nearest {
+                Some((_, _, nearest_distance)) if nearest_distance <= distance => nearest,
+                _ => Some((key, circle, distance)),
+            })
+            .map(|(key, circle, _)| (key, circle))
+    }
+
+    /// Find every circle on the `mask` layers whose center is within
+    /// `radius` of `center`.
+    pub fn within_radius(&self, center: Vector2, radius: f32, mask: LayerMask) -> Vec<Key<Circle>> {
+        keys(self.within_radius_circles(center, radius, mask))
+    }
+
+    /// Like `within_radius`, with the circles.
+    pub fn within_radius_circles(&self, center: Vector2, radius: f32, mask: LayerMask) -> Vec<(Key<Circle>, &Circle)> {
+        self.circles.iter()
+            .filter(|(_, circle)| mask.contains(&circle.layer)
+                && (circle.center - center).length_sqr() <= radius * radius)
+            .map(|(&key, circle)| (key, circle))
+            .collect()
+    }
+
+    /// Find the nearest circle on the `mask` layers hit by a ray.
+    ///
+    /// Only circles within `max_dist` along the ray count.
+    pub fn raycast(&self, origin: Vector2, dir: Vector2, max_dist: f32, mask: LayerMask) -> Option<RayHit> {
+        if dir.length_sqr() == 0. { return None; }
+        let dir = dir.normalized();
+        //  the bounding box of the ray, to skip far circles cheaply
+        let end = origin + dir * max_dist;
+        let bounds = Aabb {
+            min: Vector2::new(origin.x.min(end.x), origin.y.min(end.y)),
+            max: Vector2::new(origin.x.max(end.x), origin.y.max(end.y)),
+            layer: Layer::new(0),
+        };
+
+        let mut ret: Option<RayHit> = None;
+        for (&key, circle) in &self.circles {
+            if !mask.contains(&circle.layer) || !circle.intersects_aabb(&bounds) { continue; }
+            if let Some(distance) = circle.ray_distance(origin, dir) {
+                if distance <= max_dist && ret.is_none_or(|hit| distance < hit.distance) {
+                    ret = Some(RayHit { key, point: origin + dir * distance, distance });
+                }
+            }
+        }
+        ret
+    }
+
+    /// Find the first circle on the `mask` layers that `circle` hits
+    /// while moving by `motion`, with the fraction of `motion` covered
+    /// at impact.
+    ///
+    /// Circles that already overlap `circle` are hit at fraction 0. A
+    /// circle of the world hits itself unless `mask` excludes its layer.
+    pub fn swept_collision(&self, circle: &Circle, motion: Vector2, mask: LayerMask) -> Option<(Key<Circle>, f32)> {
+        let length = motion.length();
+        let dir = if length > 0. { motion / length } else { Vector2::zero() };
+
+        let mut ret: Option<(Key<Circle>, f32)> = None;
+        for (&key, other) in &self.circles {
+            if !mask.contains(&other.layer) { continue; }
+            //  a moving circle hits another where its center enters the
+            //  other circle grown by its radius
+            let grown = Circle::new(other.center, other.radius + circle.radius, other.layer);
+            let fraction = if length > 0. {
+                match grown.ray_distance(circle.center, dir) {
+                    Some(distance) if distance <= length => distance / length,
+                    _ => continue,
+                }
+            } else if circle.intersects(other) {
+                0.
             } else {
-                //  only report collisions between more than 1 circles
-                if active_interval.len() > 1 {
-                    x_axis_collisions.push(active_interval);
+                continue;
+            };
+            if ret.is_none_or(|(_, first)| fraction < first) {
+                ret = Some((key, fraction));
+            }
+        }
+        ret
+    }
+
+    /// The boxes every circle touches, by the circle's layer.
+    ///
+    /// Boxes are expected to be few, like walls, so every circle is
+    /// checked against every box that overlaps it on the x axis.
+    pub fn aabb_collisions(&self) -> AabbCollisions {
+        let mut ret = AabbCollisions::new();
+        for (&key, circle) in &self.circles {
+            let touched: Vec<_> = self.aabbs.iter()
+                .filter(|(_, aabb)| aabb.intersects_x_axis(circle)
+                    && circle.intersects_aabb(aabb)
+                    && Self::layers_collide(&self.collision_matrix, circle.layer, aabb.layer))
+                .map(|(&aabb_key, _)| aabb_key)
+                .collect();
+            if !touched.is_empty() {
+                ret.insert(key, touched);
+            }
+        }
+        ret
+    }
+
+    /// Like `collisions`, but also reports how deep every overlap is.
+    pub fn collisions_detailed(&self) -> CircleContacts {
+        self.contacts(&self.collisions())
+    }
+
+    /// How deep every overlap in `collisions` is, for collisions that
+    /// were already found.
+    pub fn contacts(&self, collisions: &CircleCollisions) -> CircleContacts {
+        collisions.iter()
+            .map(|(&key, collided)| {
+                let circle = &self.circles[key];
+                let contacts = collided.iter()
+                    .map(|&other| {
+                        let other_circle = &self.circles[other];
+                        let offset = circle.center - other_circle.center;
+                        let distance = offset.length();
+                        //  coincident circles still split, each to its own side
+                        let normal = if distance > 0. {
+                            offset / distance
+                        } else if key < other {
+                            Vector2::new(1., 0.)
+                        } else {
+                            Vector2::new(-1., 0.)
+                        };
+                        Contact { other, normal, depth: circle.radius + other_circle.radius - distance }
+                    })
+                    .collect();
+                (key, contacts)
+            })
+            .collect()
+    }
+
+    /// Pairs of circles that share a grid cell, each pair once.
+    fn candidates_grid(&self, cell_size: Option<f32>) -> Vec<(Key<Circle>, Key<Circle>)> {
+        //  the default cell fits the largest circle
+        let cell_size = cell_size.unwrap_or_else(|| {
+            2. * self.circles.values().map(|circle| circle.radius).fold(0., f32::max)
+        });
+        //  degenerate cells would put every circle in its own bucket
+        let cell_size = if cell_size > 0. { cell_size } else { 1. };
+        let cell = |v: f32| (v / cell_size).floor() as i32;
+
+        //  bucket every circle into the cells its bounding box covers
+        let mut grid = HashMap::<(i32, i32), Vec<_>>::new();
+        for (&key, circle) in &self.circles {
+            for x in cell(circle.center.x - circle.radius)..=cell(circle.center.x + circle.radius) {
+                for y in cell(circle.center.y - circle.radius)..=cell(circle.center.y + circle.radius) {
+                    grid.entry((x, y)).or_default().push(key);
                 }
-                active_interval = vec![(key, circle)];
             }
         }
-        x_axis_collisions.push(active_interval);
-        
-        let mut ret = HashMap::new();
-        for interval in &x_axis_collisions {
-            for (key, value) in Self::collisions_naive(&self.collision_matrix, interval) {
-                ret.insert(key, value);
+
+        //  circles sharing several cells are paired once
+        let mut checked = HashSet::new();
+        let mut ret = vec![];
+        for bucket in grid.values() {
+            for (i, &key) in bucket.iter().enumerate() {
+                for &other_key in &bucket[i + 1..] {
+                    if !self.circles[key].can_collide(&self.circles[other_key]) { continue; }
+                    if checked.insert((key.min(other_key), key.max(other_key))) {
+                        ret.push((key, other_key));
+                    }
+                }
             }
         }
         ret
     }
+
+    /// The axis the circles' centers spread along the most, which
+    /// prunes the most pairs.
+    fn sweep_axis(&self) -> Axis {
+        let count = self.circles.len() as f32;
+        if count == 0. { return Axis::X; }
+        let mean = self.circles.values().fold(Vector2::zero(), |sum, circle| sum + circle.center) / count;
+        let variance = self.circles.values()
+            .fold(Vector2::zero(), |sum, circle| sum + (circle.center - mean) * (circle.center - mean)) / count;
+        if variance.y > variance.x { Axis::Y } else { Axis::X }
+    }
+
+    /// Bring the sorted circles up to date with the world, sorted by
+    /// their lower edge on `axis`, and return how many comparisons
+    /// re-sorting the circles from the last sweep took.
+    ///
+    /// Circles barely move between frames, so the last order is nearly
+    /// sorted and an insertion sort fixes it in about linear time.
+    /// Circles that were added since are sorted on their own and merged in.
+    /// After the axis changes, every circle is sorted again.
+    fn sort_along(&self, axis: Axis) -> usize {
+        let lower = |key: &Key<Circle>| {
+            let circle = &self.circles[*key];
+            axis.of(circle.center) - circle.radius
+        };
+        let mut cache = self.sorted.borrow_mut();
+        let (sorted_axis, sorted) = &mut *cache;
+        if *sorted_axis != axis {
+            *sorted_axis = axis;
+            sorted.clear();
+        }
+        sorted.retain(|&key| self.circles.get(key).is_some());
+        let comparisons = insertion_sort_by(sorted, |a, b| lower(a) < lower(b));
+
+        if sorted.len() < self.circles.len() {
+            let known: HashSet<_> = sorted.iter().copied().collect();
+            let mut added: Vec<_> = self.circles.keys().filter(|key| !known.contains(key)).collect();
+            added.sort_by(|a, b| lower(a).partial_cmp(&lower(b)).unwrap());
+            //  the stable sort merges the two sorted runs in linear time
+            sorted.extend(added);
+            sorted.sort_by(|a, b| lower(a).partial_cmp(&lower(b)).unwrap());
+        }
+        comparisons
+    }
+
+    /// Pairs of circles that overlap on the sweep axis, each pair once.
+    fn candidates_sweep_and_prune(&self) -> Vec<(Key<Circle>, Key<Circle>)> {
+        let axis = self.sweep_axis();
+        self.sort_along(axis);
+        let cache = self.sorted.borrow();
+        let (_, sorted) = &*cache;
+
+        //  sweep, keeping only the circles that still reach the current one
+        let mut ret = vec![];
+        let mut active_interval: Vec<(Key<Circle>, &Circle)> = vec![];
+        for &key in sorted.iter() {
+            let circle = &self.circles[key];
+            //  the active circles start further back, so any of them that
+            //  does not overlap the current one never will again
+            active_interval.retain(|other| other.1.intersects_axis(circle, axis));
+            ret.extend(active_interval.iter()
+                .filter(|other| other.1.can_collide(circle))
+                .map(|other| (other.0, key)));
+            active_interval.push((key, circle));
+        }
+        ret
+    }
+}
+
+/// The keys of the circles a query found.
+fn keys(found: Vec<(Key<Circle>, &Circle)>) -> Vec<Key<Circle>> {
+    found.into_iter().map(|(key, _)| key).collect()
+}
+
+/// Sort with an insertion sort, which takes about linear time on
+/// nearly sorted slices. Returns how many comparisons it took.
+fn insertion_sort_by<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], mut is_less: F) -> usize {
+    let mut comparisons = 0;
+    for i in 1..v.len() {
+        let mut j = i;
+        while j > 0 {
+            comparisons += 1;
+            if !is_less(&v[j], &v[j - 1]) { break; }
+            v.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+    comparisons
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn random_world(broadphase: Broadphase, count: usize) -> World {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(count as u64);
+        let mut collision_matrix = CollisionMatrix::new();
+        collision_matrix.insert(Layer::new(0), LayerMask::new(vec![Layer::new(0), Layer::new(1)]));
+        collision_matrix.insert(Layer::new(1), LayerMask::empty());
+        let mut w = World::with_broadphase(collision_matrix, broadphase);
+        for i in 0..count {
+            let circle = Circle::new(
+                Vector2::new(rng.gen_range(0.0..500.), rng.gen_range(0.0..500.)),
+                rng.gen_range(0.0..if i % 10 == 0 { 100. } else { 10. }),
+                Layer::new(rng.gen_range(0..3)),
+            );
+            w.circles.insert(Circle { is_static: i % 4 == 0, ..circle });
+        }
+        w
+    }
+
+    fn sorted(mut collisions: CircleCollisions) -> CircleCollisions {
+        for collided in collisions.values_mut() { collided.sort(); }
+        collisions
+    }
+
+    #[test]
+    fn test_broadphases_match_naive() {
+        for &count in &[0, 1, 10, 100, 300] {
+            let grid = random_world(Broadphase::Grid { cell_size: None }, count);
+            let small_grid = random_world(Broadphase::Grid { cell_size: Some(3.) }, count);
+            let sweep = random_world(Broadphase::SweepAndPrune, count);
+            let all = grid.circles.iter().map(|(&key, circle)| (key, circle)).collect();
+            let naive = sorted(World::collisions_naive(&grid.collision_matrix, &all));
+
+            assert_eq!(sorted(grid.collisions()), naive);
+            assert_eq!(sorted(small_grid.collisions()), naive);
+            assert_eq!(sorted(sweep.collisions()), naive);
+        }
+    }
+
+    #[test]
+    fn test_sweep_and_prune_prunes_wide_circles() {
+        let mut w = World::with_broadphase(CollisionMatrix::new(), Broadphase::SweepAndPrune);
+        let count = 1000;
+        w.circles.insert(Circle::new(Vector2::new(0., 0.), 10., Layer::new(0)));
+        for i in 0..count {
+            w.circles.insert(Circle::new(Vector2::new(i as f32 * 3., 0.), 1., Layer::new(0)));
+        }
+        //  a huge circle in the middle of the row
+        w.circles.insert(Circle::new(Vector2::new(count as f32 * 1.5, 0.), count as f32 * 2., Layer::new(0)));
+
+        //  every small circle is paired with the huge one and its few neighbors
+        assert!(w.candidates_sweep_and_prune().len() < 3 * count);
+    }
+
+    #[test]
+    fn test_sweep_and_prune_resorts_incrementally() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let count = 1000;
+        let mut w = random_world(Broadphase::SweepAndPrune, count);
+        //  the first sort has nothing to start from
+        assert_eq!(w.sort_along(Axis::X), 0);
+        for _ in 0..10 {
+            //  circles move a little every frame, so few swap places
+            for circle in w.circles.values_mut() {
+                circle.center.x += rng.gen_range(-0.05..0.05);
+            }
+            let comparisons = w.sort_along(Axis::X);
+            assert!(comparisons < 2 * count, "{} comparisons", comparisons);
+            let cache = w.sorted.borrow();
+            let (_, sorted) = &*cache;
+            assert_eq!(sorted.len(), count);
+            let left = |key: &Key<Circle>| w.circles[*key].center.x - w.circles[*key].radius;
+            assert!(sorted.windows(2).all(|pair| left(&pair[0]) <= left(&pair[1])));
+        }
+    }
+
+    #[test]
+    fn test_sweep_and_prune_follows_added_and_removed_circles() {
+        let mut w = random_world(Broadphase::SweepAndPrune, 100);
+        w.collisions();
+        let keys: Vec<_> = w.circles.keys().collect();
+        for &key in keys.iter().step_by(3) {
+            w.circles.remove(key);
+        }
+        for i in 0..20 {
+            w.circles.insert(Circle::new(Vector2::new(i as f32 * 25., 250.), 15., Layer::new(0)));
+        }
+
+        let all = w.circles.iter().map(|(&key, circle)| (key, circle)).collect();
+        let naive = sorted(World::collisions_naive(&w.collision_matrix, &all));
+        assert_eq!(sorted(w.collisions()), naive);
+        assert_eq!(w.sorted.borrow().1.len(), w.circles.len());
+    }
+
+    #[test]
+    fn test_sweep_along_wider_spread() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut w = World::with_broadphase(CollisionMatrix::new(), Broadphase::SweepAndPrune);
+        for _ in 0..200 {
+            let center = Vector2::new(rng.gen_range(0.0..20.), rng.gen_range(0.0..1000.));
+            w.circles.insert(Circle::new(center, rng.gen_range(0.0..10.), Layer::new(0)));
+        }
+        assert_eq!(w.sweep_axis(), Axis::Y);
+
+        let all = w.circles.iter().map(|(&key, circle)| (key, circle)).collect();
+        let naive = sorted(World::collisions_naive(&w.collision_matrix, &all));
+        assert_eq!(sorted(w.collisions()), naive);
+        assert_eq!(w.sorted.borrow().0, Axis::Y);
+
+        //  turn the world on its side
+        for circle in w.circles.values_mut() {
+            circle.center = Vector2::new(circle.center.y, circle.center.x);
+        }
+        assert_eq!(w.sweep_axis(), Axis::X);
+        let all = w.circles.iter().map(|(&key, circle)| (key, circle)).collect();
+        let naive = sorted(World::collisions_naive(&w.collision_matrix, &all));
+        assert_eq!(sorted(w.collisions()), naive);
+    }
+
+    #[test]
+    fn test_triggers_report_separately() {
+        let mut w = World::new(CollisionMatrix::new());
+        let trigger = w.circles.insert(Circle::new_trigger(Vector2::new(0., 0.), 5., Layer::new(0)));
+        let solid = w.circles.insert(Circle::new(Vector2::new(3., 0.), 1., Layer::new(0)));
+        //  triggers do not sense each other
+        w.circles.insert(Circle::new_trigger(Vector2::new(-3., 0.), 5., Layer::new(0)));
+
+        let (collisions, triggers) = w.collisions_and_triggers();
+        assert!(collisions.is_empty());
+        assert_eq!(w.collision_pairs().count(), 0);
+        assert_eq!(triggers.len(), 1);
+        assert_eq!(triggers[&trigger], vec![solid]);
+
+        let other = w.circles.insert(Circle::new(Vector2::new(4., 0.), 1., Layer::new(0)));
+        assert_eq!(sorted(w.collisions()), sorted(vec![(solid, vec![other]), (other, vec![solid])].into_iter().collect()));
+        assert_eq!(w.triggers()[&trigger], vec![solid, other]);
+    }
+
+    #[test]
+    fn test_static_circles_skip_each_other() {
+        for &broadphase in &[Broadphase::SweepAndPrune, Broadphase::Grid { cell_size: None }] {
+            let mut w = World::with_broadphase(CollisionMatrix::new(), broadphase);
+            let a = w.circles.insert(Circle::new_static(Vector2::new(0., 0.), 2., Layer::new(0)));
+            let b = w.circles.insert(Circle::new_static(Vector2::new(1., 0.), 2., Layer::new(0)));
+            let collisions = w.collisions();
+            assert!(collisions.is_empty());
+
+            let c = w.circles.insert(Circle::new(Vector2::new(0., 1.), 2., Layer::new(0)));
+            let collisions = sorted(w.collisions());
+            assert_eq!(collisions[&c], vec![a, b]);
+            assert_eq!(collisions[&a], vec![c]);
+            assert_eq!(collisions[&b], vec![c]);
+        }
+    }
+
+    #[test]
+    fn test_circle_helpers() {
+        let circle = Circle::new(Vector2::new(1., 1.), 2., Layer::new(0));
+
+        assert!(circle.contains_point(Vector2::new(1., 1.)));
+        assert!(circle.contains_point(Vector2::new(2., 2.)));
+        //  the boundary is inside
+        assert!(circle.contains_point(Vector2::new(3., 1.)));
+        assert!(circle.contains_point(Vector2::new(1., -1.)));
+        assert!(!circle.contains_point(Vector2::new(3.01, 1.)));
+
+        assert_eq!(circle.area(), 4. * std::f32::consts::PI);
+        assert_eq!(circle.circumference(), 4. * std::f32::consts::PI);
+    }
+
+    #[test]
+    fn test_layer_mask_operators() {
+        let (a, b, c) = (Layer::new(0), Layer::new(1), Layer::new(2));
+
+        let mask = a | b;
+        assert!(mask.contains(&a) && mask.contains(&b) && !mask.contains(&c));
+        assert_eq!(mask, LayerMask::new(vec![a, b]));
+        assert_eq!(mask | c, LayerMask::new(vec![a, b, c]));
+        assert_eq!(c | mask, mask | LayerMask::from(c));
+
+        let mut mask = LayerMask::from(a);
+        mask |= b;
+        mask |= LayerMask::from(c);
+        assert_eq!(mask, a | b | c);
+    }
+
+    #[test]
+    fn test_circle_aabb_intersection() {
+        let aabb = Aabb { min: Vector2::new(0., 0.), max: Vector2::new(4., 2.), layer: Layer::new(0) };
+        let circle = |x, y, radius| Circle::new(Vector2::new(x, y), radius, Layer::new(0));
+
+        //  inside, touching an edge, and overlapping an edge
+        assert!(circle(2., 1., 0.5).intersects_aabb(&aabb));
+        assert!(circle(5., 1., 1.).intersects_aabb(&aabb));
+        assert!(circle(2., -0.5, 1.).intersects_aabb(&aabb));
+        //  near a corner on both axes but not touching it
+        assert!(!circle(4.8, 2.8, 1.).intersects_aabb(&aabb));
+        assert!(circle(4.5, 2.5, 1.).intersects_aabb(&aabb));
+        //  far away
+        assert!(!circle(10., 10., 1.).intersects_aabb(&aabb));
+    }
+
+    #[test]
+    fn test_aabb_collisions() {
+        let mut collision_matrix = CollisionMatrix::new();
+        collision_matrix.insert(Layer::new(1), LayerMask::empty());
+        let mut w = World::new(collision_matrix);
+        let wall = w.aabbs.insert(Aabb { min: Vector2::new(0., 0.), max: Vector2::new(1., 10.), layer: Layer::new(0) });
+        let a = w.circles.insert(Circle::new(Vector2::new(1.5, 5.), 1., Layer::new(0)));
+        w.circles.insert(Circle::new(Vector2::new(5., 5.), 1., Layer::new(0)));
+        //  touching, but on a layer that collides with nothing
+        w.circles.insert(Circle::new(Vector2::new(1.5, 5.), 1., Layer::new(1)));
+
+        assert_eq!(w.aabb_collisions(), [(a, vec![wall])].iter().cloned().collect());
+    }
+
+    #[test]
+    fn test_query_point() {
+        let mut w = World::new(CollisionMatrix::new());
+        let a = w.circles.insert(Circle::new(Vector2::new(0., 0.), 2., Layer::new(0)));
+        let b = w.circles.insert(Circle::new(Vector2::new(2., 0.), 2., Layer::new(0)));
+        let c = w.circles.insert(Circle::new(Vector2::new(1., 0.), 2., Layer::new(1)));
+        w.circles.insert(Circle::new(Vector2::new(4., 0.), 1., Layer::new(0)));
+
+        assert_eq!(w.query_point(Vector2::new(1., 1.), LayerMask::full()), vec![a, b, c]);
+        assert_eq!(w.query_point(Vector2::new(1., 1.), LayerMask::new(vec![Layer::new(0)])), vec![a, b]);
+        assert_eq!(w.query_point(Vector2::new(-1., 0.), LayerMask::full()), vec![a, c]);
+        assert_eq!(w.query_point(Vector2::new(10., 0.), LayerMask::full()), vec![]);
+    }
+
+    #[test]
+    fn test_nearest_and_within_radius() {
+        let mut w = World::new(CollisionMatrix::new());
+        let a = w.circles.insert(Circle::new(Vector2::new(1., 0.), 1., Layer::new(0)));
+        let b = w.circles.insert(Circle::new(Vector2::new(0., 3.), 1., Layer::new(0)));
+        let c = w.circles.insert(Circle::new(Vector2::new(0., -0.5), 1., Layer::new(1)));
+        let d = w.circles.insert(Circle::new(Vector2::new(-5., 0.), 1., Layer::new(0)));
+        let layer_0 = LayerMask::new(vec![Layer::new(0)]);
+
+        assert_eq!(w.nearest(Vector2::zero(), LayerMask::full()), Some(c));
+        assert_eq!(w.nearest(Vector2::zero(), layer_0), Some(a));
+        assert_eq!(w.nearest(Vector2::new(-3., 0.), layer_0), Some(d));
+        assert_eq!(w.nearest(Vector2::zero(), LayerMask::empty()), None);
+
+        assert_eq!(w.within_radius(Vector2::zero(), 3., LayerMask::full()), vec![a, b, c]);
+        assert_eq!(w.within_radius(Vector2::zero(), 3., layer_0), vec![a, b]);
+        assert_eq!(w.within_radius(Vector2::zero(), 0.1, layer_0), vec![]);
+    }
+
+    #[test]
+    fn test_queries_return_stored_circles() {
+        let mut w = World::new(CollisionMatrix::new());
+        let a = w.circles.insert(Circle::new(Vector2::new(1., 0.), 1., Layer::new(0)));
+        let b = w.circles.insert(Circle::new(Vector2::new(0., 2.), 2., Layer::new(0)));
+        w.circles.insert(Circle::new(Vector2::new(-5., 0.), 1., Layer::new(1)));
+        let stored = |found: Vec<(Key<Circle>, &Circle)>| found.into_iter()
+            .all(|(key, circle)| std::ptr::eq(circle, &w.circles[key]));
+
+        let found = w.query_point_circles(Vector2::new(0.5, 0.5), LayerMask::full());
+        assert_eq!(found.iter().map(|&(key, _)| key).collect::<Vec<_>>(), vec![a, b]);
+        assert!(stored(found));
+
+        let found = w.within_radius_circles(Vector2::zero(), 3., LayerMask::full());
+        assert_eq!(found.len(), 2);
+        assert!(stored(found));
+
+        let (key, circle) = w.nearest_circle(Vector2::new(0., 4.), LayerMask::full()).unwrap();
+        assert_eq!(key, b);
+        assert!(std::ptr::eq(circle, &w.circles[b]));
+        assert_eq!(circle.radius, 2.);
+    }
+
+    #[test]
+    fn test_collision_pairs() {
+        let mut w = World::new(CollisionMatrix::new());
+        let n = 6;
+        for i in 0..n {
+            w.circles.insert(Circle::new(Vector2::new(i as f32 * 0.1, 0.), 1., Layer::new(0)));
+        }
+        w.circles.insert(Circle::new(Vector2::new(10., 0.), 1., Layer::new(0)));
+
+        let pairs: Vec<_> = w.collision_pairs().collect();
+        assert_eq!(pairs.len(), n * (n - 1) / 2);
+        assert!(pairs.iter().all(|(a, b)| a < b));
+    }
+
+    #[test]
+    fn test_collisions_detailed() {
+        let mut w = World::new(CollisionMatrix::new());
+        let a = w.circles.insert(Circle::new(Vector2::new(0., 0.), 1.5, Layer::new(0)));
+        let b = w.circles.insert(Circle::new(Vector2::new(0., 1.), 0.5, Layer::new(0)));
+
+        let contacts = w.collisions_detailed();
+        assert_eq!(contacts[&a], vec![Contact { other: b, normal: Vector2::new(0., -1.), depth: 1. }]);
+        assert_eq!(contacts[&b], vec![Contact { other: a, normal: Vector2::new(0., 1.), depth: 1. }]);
+
+        //  coincident circles split along opposite normals
+        w.circles[b].center = Vector2::new(0., 0.);
+        let contacts = w.collisions_detailed();
+        assert_eq!(contacts[&a][0].normal, Vector2::new(1., 0.));
+        assert_eq!(contacts[&b][0].normal, Vector2::new(-1., 0.));
+        assert_eq!(contacts[&a][0].depth, 2.);
+    }
+
+    #[test]
+    fn test_raycast() {
+        let mut w = World::new(CollisionMatrix::new());
+        let far = w.circles.insert(Circle::new(Vector2::new(10., 0.), 1., Layer::new(0)));
+        let near = w.circles.insert(Circle::new(Vector2::new(5., 0.), 1., Layer::new(0)));
+        let other_layer = w.circles.insert(Circle::new(Vector2::new(2., 0.), 1., Layer::new(1)));
+        let mask = LayerMask::new(vec![Layer::new(0)]);
+
+        let hit = w.raycast(Vector2::zero(), Vector2::new(2., 0.), 100., mask).unwrap();
+        assert_eq!(hit.key, near);
+        assert_eq!(hit.point, Vector2::new(4., 0.));
+        assert_eq!(hit.distance, 4.);
+
+        //  the nearer circle is excluded by the mask
+        let hit = w.raycast(Vector2::zero(), Vector2::new(1., 0.), 100., LayerMask::full()).unwrap();
+        assert_eq!(hit.key, other_layer);
+
+        w.circles.remove(near);
+        assert_eq!(w.raycast(Vector2::zero(), Vector2::new(1., 0.), 100., mask).unwrap().key, far);
+        assert_eq!(w.raycast(Vector2::zero(), Vector2::new(1., 0.), 5., mask), None);
+    }
+
+    #[test]
+    fn test_raycast_miss() {
+        let mut w = World::new(CollisionMatrix::new());
+        w.circles.insert(Circle::new(Vector2::new(5., 0.), 1., Layer::new(0)));
+        w.circles.insert(Circle::new(Vector2::new(0., 5.), 1., Layer::new(0)));
+
+        assert_eq!(w.raycast(Vector2::zero(), Vector2::new(-1., 0.), 100., LayerMask::full()), None);
+        assert_eq!(w.raycast(Vector2::zero(), Vector2::new(1., 1.), 100., LayerMask::full()), None);
+    }
+
+    #[test]
+    fn test_integrate() {
+        let mut w = World::new(CollisionMatrix::new());
+        let moving = w.circles.insert(Circle {
+            velocity: Vector2::new(2., -4.),
+            ..Circle::new(Vector2::new(1., 1.), 1., Layer::new(0))
+        });
+        let still = w.circles.insert(Circle::new(Vector2::new(1., 1.), 1., Layer::new(0)));
+
+        w.integrate(0.5);
+        assert_eq!(w.circles[moving].center, Vector2::new(2., -1.));
+        assert_eq!(w.circles[still].center, Vector2::new(1., 1.));
+    }
+
+    #[test]
+    fn test_swept_collision() {
+        let mut w = World::new(CollisionMatrix::new());
+        let still = w.circles.insert(Circle::new(Vector2::new(10., 0.), 1., Layer::new(0)));
+        let fast = Circle::new(Vector2::new(0., 0.), 0.5, Layer::new(1));
+        let mask = LayerMask::from(Layer::new(0));
+
+        //  both ends of the motion are clear of the circle, the path is not
+        assert!(!Circle { center: Vector2::new(20., 0.), ..fast }.intersects(&w.circles[still]));
+        let (key, fraction) = w.swept_collision(&fast, Vector2::new(20., 0.), mask).unwrap();
+        assert_eq!(key, still);
+        assert_eq!(fraction, 8.5 / 20.);
+
+        assert_eq!(w.swept_collision(&fast, Vector2::new(5., 0.), mask), None);
+        assert_eq!(w.swept_collision(&fast, Vector2::new(0., 20.), mask), None);
+        assert_eq!(w.swept_collision(&fast, Vector2::new(20., 0.), LayerMask::empty()), None);
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let mut w = World::new(CollisionMatrix::new());
+        w.circles.insert(Circle::new(Vector2::new(5., 5.), 1., Layer::new(0)));
+        //  only the center counts, and the edges are inside
+        w.circles.insert(Circle::new(Vector2::new(10., 0.), 3., Layer::new(0)));
+        let left = w.circles.insert(Circle::new(Vector2::new(-1., 5.), 1., Layer::new(0)));
+        let below = w.circles.insert(Circle::new(Vector2::new(5., 11.), 1., Layer::new(0)));
+        assert_eq!(w.out_of_bounds(), vec![]);
+
+        w.bounds = Some(Rectangle::new(0., 0., 10., 10.));
+        assert_eq!(w.out_of_bounds(), vec![left, below]);
+    }
+
+    #[test]
+    fn test_collision_tracker() {
+        let mut w = World::new(CollisionMatrix::new());
+        let mut tracker = CollisionTracker::new();
+        let still = w.circles.insert(Circle::new(Vector2::new(0., 0.), 1., Layer::new(0)));
+        let moving = w.circles.insert(Circle::new(Vector2::new(-5., 0.), 1., Layer::new(0)));
+        let mut frame = |w: &mut World, x: f32| {
+            w.circles[moving].center.x = x;
+            tracker.update(&w.collisions());
+            let mut entered: Vec<_> = tracker.entered().collect();
+            let mut exited: Vec<_> = tracker.exited().collect();
+            entered.sort();
+            exited.sort();
+            (entered, exited)
+        };
+        let both = {
+            let mut both = vec![(still, moving), (moving, still)];
+            both.sort();
+            both
+        };
+
+        assert_eq!(frame(&mut w, -3.), (vec![], vec![]));
+        assert_eq!(frame(&mut w, -1.), (both.clone(), vec![]));
+        assert_eq!(frame(&mut w, 0.), (vec![], vec![]));
+        assert_eq!(frame(&mut w, 1.), (vec![], vec![]));
+        assert_eq!(frame(&mut w, 3.), (vec![], both));
+        assert_eq!(frame(&mut w, 5.), (vec![], vec![]));
+    }
+
     #[test]
     fn test_2_body_collision() {
         let mut w = World::new(CollisionMatrix::new());
-        let a = w.circles.insert(Circle { center: Vector2::new(5., 4.), radius: 2., layer: Layer::new(0) } );
-        let b = w.circles.insert(Circle { center: Vector2::new(6., 6.), radius: 1., layer: Layer::new(0) } );
+        let a = w.circles.insert(Circle::new(Vector2::new(5., 4.), 2., Layer::new(0)) );
+        let b = w.circles.insert(Circle::new(Vector2::new(6., 6.), 1., Layer::new(0)) );
         
         assert_eq!(w.collisions(), [
             (a, vec![b]),
@@ -161,12 +1205,12 @@ mod tests {
     #[test]
     fn test_3_body_collision() {
         let mut w = World::new(CollisionMatrix::new());
-        let a = w.circles.insert(Circle { center: Vector2::new(5., 4.), radius: 2., layer: Layer::new(0) } );
-        let b = w.circles.insert(Circle { center: Vector2::new(7., 6.), radius: 1., layer: Layer::new(0) } );
-        let c = w.circles.insert(Circle { center: Vector2::new(3., 7.), radius: 2., layer: Layer::new(0) } );
+        let a = w.circles.insert(Circle::new(Vector2::new(5., 4.), 2., Layer::new(0)) );
+        let b = w.circles.insert(Circle::new(Vector2::new(7., 6.), 1., Layer::new(0)) );
+        let c = w.circles.insert(Circle::new(Vector2::new(3., 7.), 2., Layer::new(0)) );
         
         assert_eq!(w.collisions(), [
-            (a, vec![c, b]),
+            (a, vec![b, c]),
             (b, vec![a]),
             (c, vec![a]),
         ].iter().cloned().collect());
@@ -174,8 +1218,8 @@ mod tests {
         w.circles.get_mut(c).unwrap().radius += 2.;
         
         assert_eq!(w.collisions(), [
-            (a, vec![c, b]),
-            (b, vec![c, a]),
+            (a, vec![b, c]),
+            (b, vec![a, c]),
             (c, vec![a, b]),
         ].iter().cloned().collect());
     }
@@ -184,6 +1228,7 @@ mod tests {
 pub mod prelude {
     pub use super::{
         Circle,
+        CollisionTracker,
         CollisionMatrix,
     };
 }
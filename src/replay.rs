@@ -0,0 +1,125 @@
+//! Recording a simulation, to run it again exactly.
+//!
+//! A seeded simulation that is given the same timesteps and inputs
+//! runs the same, so a replay log only needs the starting config,
+//! the timestep of every step and the inputs between the steps.
+//!
+//! # Example
+//!
+//! ```
+//! let mut log = ReplayLog::new(SimulationConfig::default());
+//! let mut sim = log.start();
+//! log.input(&mut sim, Input::SpawnBlob(BlobBuilder::new()));
+//! log.step(&mut sim, 1. / 60.);
+//!
+//! let replayed = Simulation::replay(&log);
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{
+    keyed_set::prelude::*,
+    simulation::prelude::*,
+};
+
+
+/// A user action on a simulation.
+#[derive(Debug, Clone)]
+pub enum Input {
+    SpawnBlob(BlobBuilder),
+    InsertFood { pos: Vector2 },
+    Move { blob: Key<Blob>, pos: Vector2 },
+    /// Selecting does not change the simulation, but it is recorded
+    /// so the replay shows what the user saw.
+    Select { pos: Vector2 },
+    SetPaused(bool),
+    SetTimeScale(f32),
+    Reset,
+}
+
+impl Input {
+    pub fn apply(&self, sim: &mut Simulation) {
+        match self {
+            Input::SpawnBlob(builder) => { sim.spawn_blob(builder.clone()); },
+            Input::InsertFood { pos } => { sim.insert_food(*pos); },
+            Input::Move { blob, pos } => sim.set_blob_pos(*blob, *pos),
+            Input::Select { pos } => { sim.select(*pos); },
+            Input::SetPaused(value) => sim.set_paused(*value),
+            Input::SetTimeScale(value) => sim.set_time_scale(*value),
+            Input::Reset => sim.reset(),
+        }
+    }
+}
+
+/// A record of a simulation's run, from its config.
+///
+/// Settings that are changed after the simulation is created, other
+/// than through inputs, are not recorded.
+#[derive(Debug, Clone)]
+pub struct ReplayLog {
+    config: SimulationConfig,
+    /// The timestep of every step, in order.
+    timesteps: Vec<f32>,
+    /// Every input, with the number of steps taken before it, in order.
+    inputs: Vec<(u64, Input)>,
+}
+
+impl ReplayLog {
+    /// Start a log of a simulation with the given config. Without
+    /// a seed in the config, a random one is chosen.
+    pub fn new(mut config: SimulationConfig) -> Self {
+        config.seed.get_or_insert_with(rand::random);
+        Self { config, timesteps: vec![], inputs: vec![] }
+    }
+
+    /// The config the simulation starts with, always seeded.
+    pub fn config(&self) -> &SimulationConfig { &self.config }
+
+    /// Returns the steps taken so far.
+    pub fn tick(&self) -> u64 { self.timesteps.len() as u64 }
+
+    /// Returns `(tick, timestep, input)` for every input, where the
+    /// input was applied before step number `tick`, which was
+    /// `timestep` long. Inputs after the last step have no timestep.
+    pub fn entries(&self) -> impl Iterator<Item = (u64, Option<f32>, &Input)> + '_ {
+        self.inputs.iter().map(move |(tick, input)| (*tick, self.timesteps.get(*tick as usize).copied(), input))
+    }
+
+    /// Create the simulation to record.
+    pub fn start(&self) -> Simulation { Simulation::with_config(self.config) }
+
+    /// Apply an input to the recorded simulation, and record it.
+    pub fn input(&mut self, sim: &mut Simulation, input: Input) {
+        input.apply(sim);
+        self.inputs.push((self.tick(), input));
+    }
+
+    /// Step the recorded simulation, and record the timestep.
+    pub fn step(&mut self, sim: &mut Simulation, timestep: f32) {
+        sim.step(timestep);
+        self.timesteps.push(timestep);
+    }
+}
+
+impl Simulation {
+    /// Run a new simulation through the steps and inputs recorded
+    /// in the log. It ends up the same as the recorded simulation.
+    pub fn replay(log: &ReplayLog) -> Simulation {
+        let mut sim = log.start();
+        let mut inputs = log.inputs.iter().peekable();
+        for (tick, &timestep) in log.timesteps.iter().enumerate() {
+            while let Some((_, input)) = inputs.next_if(|(input_tick, _)| *input_tick == tick as u64) {
+                input.apply(&mut sim);
+            }
+            sim.step(timestep);
+        }
+        for (_, input) in inputs {
+            input.apply(&mut sim);
+        }
+        sim
+    }
+}
+
+pub mod prelude {
+    pub use super::{Input, ReplayLog};
+}
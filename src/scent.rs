@@ -0,0 +1,142 @@
+//! Scent trails that blobs leave behind them.
+//!
+//! Space is split into square cells, each holding how strong the scent
+//! is there. Scents fade over time, so the strongest are the freshest.
+
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+
+
+type Cell = (i32, i32);
+
+/// The scents over a simulation's space.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScentField {
+    /// The width of a cell.
+    pub cell_size: f32,
+    /// The part of a scent that fades every second.
+    pub decay_rate: f32,
+    /// How much scent a blob leaves every second.
+    pub deposit_rate: f32,
+    /// How strongly blobs steer up the scent gradient. Scents are
+    /// only left behind, so by default they are not followed.
+    pub weight: f32,
+    #[cfg_attr(feature = "save", serde(with = "save_cells"))]
+    cells: HashMap<Cell, f32>,
+}
+
+impl Default for ScentField {
+    fn default() -> Self { Self::new(20.) }
+}
+
+impl ScentField {
+    /// Scents weaker than this are forgotten.
+    const MIN_SCENT: f32 = 1e-3;
+
+    pub fn new(cell_size: f32) -> Self {
+        debug_assert!(cell_size > 0.);
+        Self { cell_size, decay_rate: 0.5, deposit_rate: 1., weight: 0., cells: HashMap::new() }
+    }
+
+    fn cell(&self, pos: Vector2) -> Cell {
+        ((pos.x / self.cell_size).floor() as i32, (pos.y / self.cell_size).floor() as i32)
+    }
+
+    /// Add scent at a position.
+    pub fn deposit(&mut self, pos: Vector2, amount: f32) {
+        *self.cells.entry(self.cell(pos)).or_insert(0.) += amount;
+    }
+
+    /// Returns how strong the scent is at a position.
+    pub fn sample(&self, pos: Vector2) -> f32 {
+        self.cells.get(&self.cell(pos)).copied().unwrap_or(0.)
+    }
+
+    /// Returns the direction the scent grows stronger in at a position,
+    /// longer as it grows faster, from the neighboring cells.
+    pub fn gradient(&self, pos: Vector2) -> Vector2 {
+        let (x, y) = self.cell(pos);
+        let at = |cell: Cell| self.cells.get(&cell).copied().unwrap_or(0.);
+        Vector2::new(
+            at((x + 1, y)) - at((x - 1, y)),
+            at((x, y + 1)) - at((x, y - 1)),
+        ) / (2. * self.cell_size)
+    }
+
+    /// Fade every scent over `dt` seconds.
+    pub fn decay(&mut self, dt: f32) {
+        let factor = (-self.decay_rate * dt).exp();
+        self.cells.retain(|_, scent| {
+            *scent *= factor;
+            *scent >= Self::MIN_SCENT
+        });
+    }
+
+    pub fn clear(&mut self) { self.cells.clear(); }
+}
+
+//  JSON maps need string keys, so the cells are saved as pairs
+#[cfg(feature = "save")]
+mod save_cells {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Cell;
+
+    pub fn serialize<S: Serializer>(cells: &HashMap<Cell, f32>, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut pairs: Vec<_> = cells.iter().collect();
+        pairs.sort_by_key(|&(&cell, _)| cell);
+        serializer.collect_seq(pairs)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<Cell, f32>, D::Error> {
+        Ok(Vec::<(Cell, f32)>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deposit_and_sample() {
+        let mut field = ScentField::new(10.);
+        field.deposit(Vector2::new(5., 5.), 1.);
+        field.deposit(Vector2::new(9., 1.), 2.);
+        assert_eq!(field.sample(Vector2::new(0., 0.)), 3.);
+        assert_eq!(field.sample(Vector2::new(15., 5.)), 0.);
+        assert_eq!(field.sample(Vector2::new(-5., 5.)), 0.);
+    }
+
+    #[test]
+    fn test_scent_decays_to_zero() {
+        let mut field = ScentField::new(10.);
+        let pos = Vector2::new(5., 5.);
+        field.deposit(pos, 1.);
+        let mut last = field.sample(pos);
+        for _ in 0..100 {
+            field.decay(0.5);
+            let scent = field.sample(pos);
+            assert!(scent < last || scent == 0.);
+            last = scent;
+        }
+        assert_eq!(last, 0.);
+        assert!(field.cells.is_empty());
+    }
+
+    #[test]
+    fn test_gradient_points_to_stronger_scent() {
+        let mut field = ScentField::new(10.);
+        field.deposit(Vector2::new(25., 5.), 1.);
+        let gradient = field.gradient(Vector2::new(15., 5.));
+        assert!(gradient.x > 0.);
+        assert_eq!(gradient.y, 0.);
+    }
+}
+
+pub mod prelude {
+    pub use super::ScentField;
+}
@@ -12,32 +12,44 @@
 //! ```
 //! use crate::simulation::prelude::*;
 //! 
-//! let mut sim = Simulation::new(SimulationConfig {
-//!     size: Vector2::new(600., 800.)
+//! let mut sim = Simulation::with_config(SimulationConfig {
+//!     size: Vector2::new(600., 800.),
+//!     ..SimulationConfig::default()
 //! });
 //! 
-//! sim.insert_blob(Blob::new());
+//! sim.spawn_blob(BlobBuilder::new());
 //! ```
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+#[cfg(feature = "save")]
+use std::io;
 
 use rand::prelude::*;
+use rand_chacha::ChaCha12Rng;
 
 use raylib::prelude::*;
 
 use crate::{
     keyed_set::prelude::*,
     physics::{self, prelude::*},
-    window::DrawingContext,
+    genome::prelude::*,
+    stats::prelude::*,
+    history::prelude::*,
+    spawner::prelude::*,
+    steering::prelude::*,
     math,
 };
+#[cfg(feature = "scent")]
+use crate::scent::prelude::*;
+#[cfg(feature = "render")]
+use crate::window::DrawingContext;
 
 
 /// Returns a vector2 with x in [0,1) and y in [0,1)
-fn random_vector2() -> Vector2 { Vector2::new(random(), random()) }
+fn random_vector2<R: Rng + ?Sized>(rng: &mut R) -> Vector2 { Vector2::new(rng.gen(), rng.gen()) }
 
 /// Returns -1 for very different colors and 1 for same color
-fn color_similarity(a: &Color, b: &Color) -> f32 {
+pub(crate) fn color_similarity(a: &Color, b: &Color) -> f32 {
     let a = a.color_to_hsv();
     let b = b.color_to_hsv();
     let angle_difference = {
@@ -51,91 +63,706 @@ fn color_similarity(a: &Color, b: &Color) -> f32 {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
 pub struct Blob {
     pub name: Option<String>,
     pub alive_time: f32,
 
-    pub speed: f32,
-    pub rotation_speed: f32,
-    radius: f32,
+    /// The heritable properties. The sight depth is also in the
+    /// physics world, so it is set through the blob.
+    genome: Genome,
     pub color: Color,
 
-    sight_depth: f32, 
-    pub pov: f32, 
-    pub favorite_color: Color, 
-    pub color_attraction: f32,
-    pub color_repulsion: f32,
-
     pos: Vector2,
+    radius: f32,
     pub direction: Vector2,
     circle: Key<Circle>,
     sight_circle: Key<Circle>,
 
+    pub hunger: f32,
+
+    /// How long the blob has been well-fed, toward reproducing.
+    pub well_fed_time: f32,
+
+    /// The blobs this blob was born to. They may have been removed since.
+    pub parents: Vec<Key<Blob>>,
+    /// How many ancestors the blob has in its longest line, or 0 if
+    /// it has no parents.
+    pub generation: u32,
+}
+
+/// Every stat of a blob at one moment, for showing or checking it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlobSnapshot {
+    pub pos: Vector2,
+    pub direction: Vector2,
+    pub radius: f32,
+    pub max_radius: f32,
+    pub color: Color,
+    pub favorite_color: Color,
+
+    pub speed: f32,
+    pub rotation_speed: f32,
+    pub pov: f32,
+    pub sight_depth: f32,
+    pub color_attraction: f32,
+    pub color_repulsion: f32,
+    pub steering: Steering,
+    pub role: Role,
+
     pub hunger: f32,
     pub max_hunger: f32,
-    //  let h1 be the hunger after eating a food
-    //  h1 = max( (h0 - hunger_reduction*h_max) / (1 + hunger_division),  0 )
+    pub hunger_rate: f32,
+    /// The hunger relative to the max hunger, from 0 when fed to 1
+    /// when starving to death.
+    pub hunger_ratio: f32,
+    pub attack: f32,
+    pub defence: f32,
     pub hunger_reduction: f32,
     pub hunger_division: f32,
 
-    pub attack: f32,
-    pub defence: f32,
+    pub alive_time: f32,
+    pub max_lifespan: f32,
+    pub well_fed_time: f32,
+    pub generation: u32,
+}
+
+/// How blobs reproduce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReproductionMode {
+    /// A well-fed blob splits into itself and a child with
+    /// a mutated copy of its genome.
+    Asexual,
+    /// Two touching well-fed blobs have a child between them,
+    /// with a mutated crossover of their genomes.
+    Sexual,
+}
+
+/// When and how blobs reproduce.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReproductionConfig {
+    pub mode: ReproductionMode,
+    /// A blob is well-fed while its hunger is below this fraction
+    /// of its max hunger.
+    pub hunger_threshold: f32,
+    /// How long a blob must stay well-fed to reproduce.
+    /// Reproducing starts the wait over.
+    pub well_fed_time: f32,
+    /// How much a child's genome differs from its parent's.
+    /// See `Genome::mutate`.
+    pub mutation_rate: f32,
+}
+
+impl Default for ReproductionConfig {
+    fn default() -> Self {
+        Self {
+            mode: ReproductionMode::Asexual,
+            hunger_threshold: 0.5,
+            well_fed_time: 5.,
+            mutation_rate: 0.1,
+        }
+    }
+}
+
+/// How fast blobs get hungry.
+///
+/// A blob's hunger grows every second by
+/// `base_rate + speed_cost * speed² + size_cost * radius²`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetabolismConfig {
+    pub base_rate: f32,
+    pub speed_cost: f32,
+    pub size_cost: f32,
+}
+
+impl Default for MetabolismConfig {
+    fn default() -> Self {
+        Self { base_rate: 1., speed_cost: 0.0001, size_cost: 0.0025 }
+    }
+}
+
+/// The weather over the whole space, which goes around a daily cycle.
+/// The cold slows blobs down, and the heat makes them hungrier.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct Environment {
+    /// From `-amplitude`, at midnight, to `amplitude`, at noon.
+    pub temperature: f32,
+    /// The part of the day that passed since midnight, from 0 to 1,
+    /// so noon is at 0.5.
+    pub time_of_day: f32,
+    /// How many seconds a day lasts.
+    pub day_length: f32,
+    /// How far the temperature swings. Without a swing the temperature
+    /// stays at 0, which changes nothing.
+    pub amplitude: f32,
+    /// The part of their speed blobs lose for every degree below 0.
+    pub cold_slowdown: f32,
+    /// The part of their hunger rate blobs gain for every degree above 0.
+    pub heat_metabolism: f32,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            temperature: 0.,
+            time_of_day: 0.,
+            day_length: 60.,
+            amplitude: 0.,
+            cold_slowdown: 0.5,
+            heat_metabolism: 0.5,
+        }
+    }
+}
+
+impl Environment {
+    /// Advance the day by `dt` seconds, and set the temperature for it.
+    pub fn advance(&mut self, dt: f32) {
+        if self.day_length > 0. {
+            self.time_of_day = (self.time_of_day + dt / self.day_length).fract();
+        }
+        self.temperature = -self.amplitude * (std::f32::consts::TAU * self.time_of_day).cos();
+    }
+
+    /// What blob speeds are multiplied by.
+    pub fn speed_factor(&self) -> f32 {
+        (1. + self.cold_slowdown * self.temperature.min(0.)).max(0.)
+    }
+
+    /// What blob hunger rates are multiplied by.
+    pub fn hunger_factor(&self) -> f32 {
+        (1. + self.heat_metabolism * self.temperature.max(0.)).max(0.)
+    }
+}
+
+/// What is left of a blob that lost a fight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub enum FightReward {
+    /// A food where the loser was.
+    DropFood,
+    /// Nothing, the winner eats the loser on the spot.
+    FeedWinner,
+    /// A food where the loser was, and the winner eats too.
+    Both,
+}
+
+/// How blobs fight.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct FightConfig {
+    /// How much size matters in a fight. The attack of a blob is
+    /// multiplied by its radius relative to the defender's, to the
+    /// power of the weight, so 0 ignores size.
+    pub size_weight: f32,
+    pub reward: FightReward,
+    /// The nutrition a winner gets from eating a loser, for every
+    /// unit of the loser's radius.
+    pub nutrition_per_radius: f32,
+}
+
+impl Default for FightConfig {
+    fn default() -> Self {
+        Self { size_weight: 1., reward: FightReward::DropFood, nutrition_per_radius: 0.1 }
+    }
+}
+
+/// How blobs grow by eating and shrink by starving.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct GrowthConfig {
+    /// How much a blob's radius grows when it eats a food of
+    /// nutrition 1, up to its max radius.
+    pub growth: f32,
+    /// A blob is starving while its hunger is above this fraction
+    /// of its max hunger.
+    pub starving_threshold: f32,
+    /// How much a starving blob's radius shrinks every second.
+    pub shrink_rate: f32,
+}
+
+impl Default for GrowthConfig {
+    fn default() -> Self {
+        Self { growth: 1., starving_threshold: 0.75, shrink_rate: 1. }
+    }
+}
+
+/// How many blobs a simulation can hold.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct PopulationConfig {
+    /// The most blobs left after a step, or `None` for no limit.
+    pub max_population: Option<usize>,
+    /// How fit a blob is. When there are too many blobs, the least
+    /// fit are culled first. It is not saved, and is the default
+    /// after loading.
+    #[cfg_attr(feature = "save", serde(skip, default = "save::default_fitness"))]
+    pub fitness: fn(&Blob) -> f32,
+}
+
+impl Default for PopulationConfig {
+    fn default() -> Self {
+        Self { max_population: None, fitness: Blob::fitness }
+    }
+}
+
+/// The properties of a blob to spawn, with defaults for the ones
+/// that are not set.
+///
+/// # Example
+///
+/// ```
+/// let key = sim.spawn_blob(BlobBuilder::new()
+///     .pos(Vector2::new(100., 100.))
+///     .speed(80.)
+///     .favorite_color(Color::GREEN));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BlobBuilder {
+    name: Option<String>,
+    pos: Vector2,
+    color: Color,
+    genome: Genome,
+    parents: Vec<Key<Blob>>,
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
 pub struct Food {
     pos: Vector2,
     circle: Key<Circle>,
+    /// How much eating the food satisfies hunger, relative to
+    /// a regular food.
+    pub nutrition: f32,
+    /// How long the food has been in the simulation.
+    pub age: f32,
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
 pub enum CircleObject {
     Blob(Key<Blob>),
     Food(Key<Food>),
     BlobSight(Key<Blob>),
 }
 
+/// The settings a simulation starts with.
+#[derive(Debug, Clone, Copy)]
+pub struct SimulationConfig {
+    /// The dimensions of the simulation's space.
+    pub size: Vector2,
+    /// The seed of the simulation's randomness, or `None` for
+    /// a different run every time.
+    pub seed: Option<u64>,
+    /// How many foods appear every second, at random positions.
+    pub food_spawn_rate: f32,
+    /// How many blobs, with slightly mutated default genomes, to start with.
+    pub initial_blobs: usize,
+    /// How many foods to start with.
+    pub initial_foods: usize,
+    /// See `ReproductionConfig::mutation_rate`.
+    pub mutation_rate: f32,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            size: Vector2::new(800., 600.),
+            seed: None,
+            food_spawn_rate: 0.,
+            initial_blobs: 0,
+            initial_foods: 0,
+            mutation_rate: ReproductionConfig::default().mutation_rate,
+        }
+    }
+}
+
+/// What happens to blobs that leave the simulation's space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub enum BorderMode {
+    /// They are put back on the border, turned back inside.
+    Bounce,
+    /// They come back in through the opposite border, going the same way.
+    Wrap,
+    /// They are put back on the border, going the same way.
+    Clamp,
+}
+
+/// How blobs turn toward where they want to go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub enum Turning {
+    /// Along the arc to the target, a part of the way that grows
+    /// with the rotation speed every second. Far targets are turned
+    /// to faster.
+    Slerp,
+    /// At a constant angular speed of the rotation speed, in radians
+    /// every second.
+    ConstantSpeed,
+}
+
+/// Why a blob died.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    /// Its hunger went over its max hunger.
+    Starved,
+    /// It lost a fight.
+    Eaten,
+    /// It lived longer than its max lifespan.
+    OldAge,
+    /// There were too many blobs, and it was among the least fit.
+    Culled,
+}
+
+/// Something that happened during a step of the simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulationEvent {
+    /// A blob was born to one parent, by splitting, or to two.
+    Born { key: Key<Blob>, parents: Vec<Key<Blob>> },
+    /// A blob died and left a food where it was.
+    Died { key: Key<Blob>, cause: DeathCause },
+    Ate { blob: Key<Blob>, food: Key<Food> },
+    Fought { winner: Key<Blob>, loser: Key<Blob> },
+    /// A food was not eaten within the food lifetime, and rotted away.
+    FoodDespawned { food: Key<Food> },
+}
+
+/// A callback for simulation events.
+pub type EventObserver = Box<dyn FnMut(&SimulationEvent)>;
+
+/// A corner of the screen.
+#[cfg(feature = "render")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Where and how the HUD is drawn.
+#[cfg(feature = "render")]
+#[derive(Debug, Clone, Copy)]
+pub struct HudConfig {
+    pub corner: Corner,
+    pub color: Color,
+    pub font_size: i32,
+    /// The space between the text and the edges of the screen.
+    pub margin: i32,
+}
+
+#[cfg(feature = "render")]
+impl Default for HudConfig {
+    fn default() -> Self {
+        Self { corner: Corner::TopRight, color: Color::BLACK, font_size: 20, margin: 10 }
+    }
+}
+
+/// The events that happened, and the callbacks to tell as they happen.
+#[derive(Default)]
+struct EventLog {
+    events: Vec<SimulationEvent>,
+    observers: Vec<EventObserver>,
+}
+
+impl EventLog {
+    fn push(&mut self, event: SimulationEvent) {
+        for observer in &mut self.observers {
+            observer(&event);
+        }
+        self.events.push(event);
+    }
+}
+
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
 pub struct Simulation {
     size: Vector2,
     blobs: KeyedSet<Blob>,
     foods: KeyedSet<Food>,
+    #[cfg_attr(feature = "save", serde(with = "save::objects"))]
     objects: HashMap<Key<Circle>, CircleObject>,
     pub physics: physics::World,
+    collision_tracker: CollisionTracker,
+    pub reproduction: ReproductionConfig,
+    pub metabolism: MetabolismConfig,
+    pub growth: GrowthConfig,
+    pub fight: FightConfig,
+    pub population: PopulationConfig,
+    /// Adds foods every step.
+    pub food_spawner: FoodSpawner,
+    /// How long foods last before they rot away, or `None` for
+    /// foods that last until they are eaten.
+    pub food_lifetime: Option<f32>,
+    paused: bool,
+    time_scale: f32,
+    /// How strongly blobs turn away from blobs they overlap.
+    pub separation_weight: f32,
+    /// How much more nearer things count when blobs steer by color,
+    /// see `ColorSteering::falloff`.
+    pub sight_falloff: f32,
+    pub border_mode: BorderMode,
+    pub turning: Turning,
+    /// Whether blobs are drawn with what they see and where they go.
+    pub show_sight: bool,
+    /// Records the population after every step, when set.
+    pub history: Option<HistoryRecorder>,
+    /// How every blob steers, instead of the steering in its genome,
+    /// when set. It is not saved.
+    #[cfg_attr(feature = "save", serde(skip))]
+    pub steering: Option<Box<dyn SteeringStrategy>>,
+    /// The trails blobs leave behind, and how they follow them.
+    #[cfg(feature = "scent")]
+    pub scent: ScentField,
+    /// The weather, which changes as the simulation steps.
+    pub environment: Environment,
+    tick: u64,
+    #[cfg_attr(feature = "save", serde(skip))]
+    events: EventLog,
+    rng: ChaCha12Rng,
 }
 
 impl Simulation {
-    const SELECTION_LAYER: physics::Layer = physics::Layer::new(4);
+    /// The layer of queries for blobs and foods, which collides with
+    /// both of them and nothing else. Custom queries through the
+    /// physics world can use its mask:
+    ///
+    /// ```
+    /// let mask = sim.physics.layer_mask(Simulation::SELECTION_LAYER);
+    /// let circles = sim.physics.within_radius(pos, 50., mask);
+    /// ```
+    pub const SELECTION_LAYER: physics::Layer = physics::Layer::new(4);
+    /// The layer of walls, which blobs cannot pass through.
+    pub const WALL_LAYER: physics::Layer = physics::Layer::new(3);
+    #[cfg(feature = "render")]
+    const WALL_COLOR: Color = Color::DARKGRAY;
 
     /// Create a simulation with a space of the given dimensions
     pub fn new(size: Vector2) -> Self {
+        Self::with_config(SimulationConfig { size, ..SimulationConfig::default() })
+    }
+
+    /// Create a simulation with a space of the given dimensions,
+    /// whose randomness is seeded.
+    ///
+    /// Simulations with the same seed that are given the same
+    /// timesteps and inputs run the same.
+    pub fn new_seeded(size: Vector2, seed: u64) -> Self {
+        Self::with_config(SimulationConfig { size, seed: Some(seed), ..SimulationConfig::default() })
+    }
+
+    /// Create a simulation from its settings.
+    pub fn with_config(config: SimulationConfig) -> Self {
+        let SimulationConfig {
+            size, seed,
+            food_spawn_rate,
+            initial_blobs, initial_foods,
+            mutation_rate,
+        } = config;
+        let rng = match seed {
+            Some(seed) => ChaCha12Rng::seed_from_u64(seed),
+            None => ChaCha12Rng::from_entropy(),
+        };
+        let mut sim = Self::with_rng(size, rng);
+        sim.food_spawner = FoodSpawner::new(FoodSpawnStrategy::FixedRate { per_second: food_spawn_rate });
+        sim.reproduction.mutation_rate = mutation_rate;
+
+        sim.reserve(initial_blobs, initial_foods);
+        for _ in 0..initial_blobs {
+            let pos = random_vector2(&mut sim.rng) * size;
+            let genome = Genome::default().mutate(&mut sim.rng, mutation_rate);
+            sim.spawn_blob(BlobBuilder::new().pos(pos).genome(genome));
+        }
+        for _ in 0..initial_foods {
+            let pos = random_vector2(&mut sim.rng) * size;
+            sim.insert_food(pos);
+        }
+        sim
+    }
+
+    fn with_rng(size: Vector2, rng: ChaCha12Rng) -> Self {
         let mut collision_matrix = CollisionMatrix::new();
-        collision_matrix.insert(Blob::LAYER, physics::LayerMask::new(vec![Food::LAYER, Blob::LAYER]));
+        collision_matrix.insert(Blob::LAYER, Food::LAYER | Blob::LAYER | Self::WALL_LAYER);
         collision_matrix.insert(Food::LAYER, physics::LayerMask::empty());
-        collision_matrix.insert(Blob::SIGHT_LAYER, physics::LayerMask::new(vec![Food::LAYER, Blob::LAYER]));
-        collision_matrix.insert(Self::SELECTION_LAYER, physics::LayerMask::new(vec![Food::LAYER, Blob::LAYER]));
+        collision_matrix.insert(Blob::SIGHT_LAYER, Food::LAYER | Blob::LAYER);
+        collision_matrix.insert(Self::SELECTION_LAYER, Food::LAYER | Blob::LAYER);
+        let mut physics = physics::World::new(collision_matrix);
+        physics.bounds = Some(Rectangle::new(0., 0., size.x, size.y));
         Self {
             size,
             blobs: KeyedSet::new(),
             foods: KeyedSet::new(),
             objects: HashMap::new(),
-            physics: physics::World::new(collision_matrix),
+            physics,
+            collision_tracker: CollisionTracker::new(),
+            reproduction: ReproductionConfig::default(),
+            metabolism: MetabolismConfig::default(),
+            growth: GrowthConfig::default(),
+            fight: FightConfig::default(),
+            population: PopulationConfig::default(),
+            food_spawner: FoodSpawner::default(),
+            food_lifetime: None,
+            paused: false,
+            time_scale: 1.,
+            separation_weight: 0.1,
+            sight_falloff: 1.,
+            border_mode: BorderMode::Bounce,
+            turning: Turning::Slerp,
+            show_sight: false,
+            history: None,
+            steering: None,
+            #[cfg(feature = "scent")]
+            scent: ScentField::default(),
+            environment: Environment::default(),
+            tick: 0,
+            events: EventLog::default(),
+            rng,
+        }
+    }
+
+    /// The random number generator of the simulation.
+    ///
+    /// Randomness that affects the simulation should be drawn from it,
+    /// so seeded simulations stay reproducible.
+    pub fn rng(&mut self) -> &mut ChaCha12Rng { &mut self.rng }
+
+    /// Write the state of the simulation as JSON, including its
+    /// random number generator, so a loaded copy runs the same.
+    ///
+    /// Events that were not taken, event observers and the fitness
+    /// function are not saved.
+    #[cfg(feature = "save")]
+    pub fn save<W: io::Write>(&self, w: W) -> io::Result<()> {
+        serde_json::to_writer(w, self)?;
+        Ok(())
+    }
+
+    /// Read a simulation written by `save`.
+    #[cfg(feature = "save")]
+    pub fn load<R: io::Read>(r: R) -> io::Result<Simulation> {
+        Ok(serde_json::from_reader(r)?)
+    }
+
+    /// Make room for at least the given number of additional blobs and foods.
+    pub fn reserve(&mut self, blobs: usize, foods: usize) {
+        //  every blob owns a body circle and a sight circle
+        let circles = 2 * blobs + foods;
+        self.blobs.reserve(blobs);
+        self.foods.reserve(foods);
+        self.objects.reserve(circles);
+        self.physics.circles.reserve(circles);
+    }
+
+    /// Remove every blob and food, keeping the settings, the event
+    /// callbacks, and the memory that was allocated.
+    ///
+    /// The randomness is not reseeded, so a reset simulation does
+    /// not replay the same run.
+    pub fn reset(&mut self) {
+        self.blobs.clear();
+        self.foods.clear();
+        self.objects.clear();
+        self.physics.circles.clear();
+        self.collision_tracker = CollisionTracker::new();
+        self.food_spawner = FoodSpawner::new(self.food_spawner.strategy);
+        self.events.events.clear();
+        self.tick = 0;
+        if let Some(history) = &mut self.history {
+            history.clear();
         }
+        #[cfg(feature = "scent")]
+        self.scent.clear();
+        self.environment.time_of_day = 0.;
+        self.environment.advance(0.);
     }
 
     /// Returns the size of the simulation's space
     pub fn size(&self) -> Vector2 { self.size }
 
+    /// Change the size of the simulation's space, and where blobs
+    /// meet its borders.
+    ///
+    /// With `rescale`, blobs and foods are moved so they keep their
+    /// positions relative to the space. Otherwise they stay where
+    /// they are, and blobs left outside are brought back by the
+    /// border mode on the next step.
+    pub fn set_size(&mut self, size: Vector2, rescale: bool) {
+        debug_assert!(size.x > 0. && size.y > 0.);
+
+        if rescale {
+            let scale = size / self.size;
+            for blob in self.blobs.values_mut() {
+                blob.set_pos(&mut self.physics, blob.pos() * scale);
+            }
+            for food in self.foods.values_mut() {
+                food.set_pos(&mut self.physics, food.pos() * scale);
+            }
+        }
+        self.size = size;
+        self.physics.bounds = Some(Rectangle::new(0., 0., size.x, size.y));
+    }
+
+    /// Returns how many steps the simulation has taken.
+    pub fn tick(&self) -> u64 { self.tick }
+
+    /// Returns true if stepping the simulation does nothing.
+    pub fn paused(&self) -> bool { self.paused }
+
+    pub fn set_paused(&mut self, value: bool) { self.paused = value; }
+
+    /// The factor every timestep is multiplied by. A scale of 0
+    /// pauses the simulation.
+    pub fn time_scale(&self) -> f32 { self.time_scale }
+
+    /// Set the time scale. Negative scales are clamped to 0.
+    pub fn set_time_scale(&mut self, value: f32) { self.time_scale = value.max(0.); }
+
     /// Draw the simulation data onto a buffer.
-    pub fn draw(&self, draw: &mut DrawingContext) {
+    #[cfg(feature = "render")]
+    pub fn draw<D: RaylibDraw>(&self, draw: &mut D) {
         //  background
         draw.clear_background(Color::RAYWHITE);
+        //  walls
+        for aabb in self.physics.aabbs.values().filter(|aabb| aabb.layer == Self::WALL_LAYER) {
+            draw.draw_rectangle_v(aabb.min, aabb.max - aabb.min, Self::WALL_COLOR);
+        }
         //  foods
-        for (_, food) in &self.foods {
+        for food in self.foods.values() {
             food.draw(draw);
         }
         //  blobs
-        for (_, blob) in &self.blobs {
-            blob.draw(draw);
+        for blob in self.blobs.values() {
+            blob.draw(draw, self.show_sight);
+        }
+    }
+
+    /// Draw the population and the frame rate in a corner of the screen.
+    #[cfg(feature = "render")]
+    pub fn draw_hud(&self, draw: &mut DrawingContext, config: &HudConfig) {
+        let stats = self.stats();
+        let lines = [
+            format!("Blobs: {}", stats.blob_count),
+            format!("Foods: {}", stats.food_count),
+            format!("FPS: {}", draw.get_fps()),
+            format!("Mean alive time: {:.1}s", stats.mean_alive_time),
+            format!("Temperature: {:.2} ({:.0}% of the day)", self.environment.temperature, self.environment.time_of_day * 100.),
+        ];
+        let height = lines.len() as i32 * config.font_size;
+        let top = match config.corner {
+            Corner::TopLeft | Corner::TopRight => config.margin,
+            Corner::BottomLeft | Corner::BottomRight => draw.get_screen_height() - config.margin - height,
+        };
+        for (i, line) in lines.iter().enumerate() {
+            let left = match config.corner {
+                Corner::TopLeft | Corner::BottomLeft => config.margin,
+                Corner::TopRight | Corner::BottomRight => {
+                    draw.get_screen_width() - config.margin - measure_text(line, config.font_size)
+                },
+            };
+            draw.draw_text(line, left, top + i as i32 * config.font_size, config.font_size, config.color);
         }
     }
 
@@ -145,135 +772,406 @@ impl Simulation {
     /// since the last step in the simulation.
     /// The step will be more accurate as the timestep is closer
     /// to 0.
+    /// The timestep is scaled by the time scale, and nothing
+    /// happens while the simulation is paused.
     pub fn step(&mut self, timestep: f32) {
         debug_assert!(timestep >= 0.);
 
-        let mut foods_to_remove = HashSet::new();
-        let mut blobs_to_remove = HashMap::new();
+        let timestep = timestep * self.time_scale;
+        if self.paused || timestep == 0. { return; }
+
+        self.environment.advance(timestep);
+
+        //  ordered, so removals and the slots they free are deterministic
+        let mut foods_to_remove = BTreeSet::new();
+        let mut blobs_to_remove = BTreeMap::new();
 
         //  run collision detection
-        let collisions = self.physics.collisions();
+        let (collisions, seen_by) = self.physics.collisions_and_triggers();
+        let contacts = self.physics.contacts(&collisions);
 
         //  prepare blob steps
         let mut steps = HashMap::new();
         for (key, blob) in &self.blobs {
-            let seen: Vec<(&CircleObject, &Color, &Vector2)> = 
-                collisions.get(&blob.sight_circle)
-                .map_or_else(|| vec![], |collided| 
-                    collided.iter()
-                    .filter_map(|&key| {
-                        let circle = self.physics.circles.get(key).unwrap();
-                        let circle_object = self.objects.get(&key).unwrap();
-                        let dir = circle.center - blob.pos();
-                        //  make sure object inside blob POV 
-                        let angle = math::unsigned_angle_vector2(dir, blob.direction).abs();
-                        if angle > blob.pov { return None; }
-
-                        let color = circle_object.color(self)?;
-                        Some((circle_object, color, &circle.center))
-                    })
-                    .collect()
-                );
-            steps.insert(*key, blob.prepare_step(seen));
-        }
-
-        //  blobs eating
-        for (_, blob) in &mut self.blobs {
-            if let Some(touched) = collisions.get(&blob.circle) {
-                for circle in touched {
-                    if let Some(&CircleObject::Food(food)) = self.objects.get(circle) {
-                        blob.feed();
-                        foods_to_remove.insert(food);
-                    }
-                }
+            let seen = seen_by.get(&blob.sight_circle)
+                .map_or_else(|| vec![], |collided| self.seen(blob, collided));
+            let mut step = match &self.steering {
+                Some(steering) => steering.decide(&blob.sense(&seen)),
+                None => blob.prepare_step(&seen, self.sight_falloff),
+            };
+            step.separation = self.separation(blob, &contacts) * self.separation_weight;
+            #[cfg(feature = "scent")]
+            {
+                step.scent = self.scent.gradient(blob.pos()) * self.scent.weight;
             }
+            steps.insert(*key, step);
         }
 
-        //  blobs fighting
-        let mut fights = HashSet::new();
-        for (blob_key, blob) in &mut self.blobs {
-            if let Some(touched) = collisions.get(&blob.circle) {
-                for circle in touched {
-                    if let Some(&CircleObject::Blob(other_blob_key)) = self.objects.get(circle) {
-                        use std::cmp::{min, max};
-                        let a = min(*blob_key, other_blob_key);
-                        let b = max(*blob_key, other_blob_key);
-                        fights.insert((a, b));
-                    }
+        //  blobs eating, only as they start touching the food
+        self.collision_tracker.update(&collisions);
+        for (circle, touched) in self.collision_tracker.entered() {
+            if let (Some(&CircleObject::Blob(blob)), Some(&CircleObject::Food(food))) = (self.objects.get(&circle), self.objects.get(&touched)) {
+                //  a food is only eaten once, by the first blob to touch it
+                if foods_to_remove.insert(food) {
+                    let nutrition = self.foods[food].nutrition;
+                    let blob_data = &mut self.blobs[blob];
+                    blob_data.feed(nutrition);
+                    blob_data.grow(&mut self.physics, self.growth.growth * nutrition);
+                    self.events.push(SimulationEvent::Ate { blob, food });
                 }
             }
         }
-        for (blob1_key, blob2_key) in fights {
-            let blob1 = self.blobs.get(blob1_key).unwrap();
-            let blob2 = self.blobs.get(blob2_key).unwrap();
-            for &(attacker, _attacker_key, defender, defender_key) in &[(blob1, blob1_key, blob2, blob2_key), (blob2, blob2_key, blob1, blob1_key)] {
-                if attacker.attack > defender.defence * (1. - defender.hunger / defender.max_hunger) {
-                    blobs_to_remove.insert(defender_key, defender.pos);
+
+        //  blobs fighting, which only predators start
+        let objects = &self.objects;
+        let touching: Vec<_> = self.physics.collision_pairs()
+            .filter_map(|(a, b)| match (objects.get(&a), objects.get(&b)) {
+                (Some(&CircleObject::Blob(a)), Some(&CircleObject::Blob(b))) => Some((a, b)),
+                _ => None,
+            })
+            .collect();
+        let feed_winners = self.fight.reward != FightReward::DropFood;
+        let mut kills = vec![];
+        for &(blob1_key, blob2_key) in &touching {
+            let blob1 = &self.blobs[blob1_key];
+            let blob2 = &self.blobs[blob2_key];
+            for &(attacker, attacker_key, defender, defender_key) in &[(blob1, blob1_key, blob2, blob2_key), (blob2, blob2_key, blob1, blob1_key)] {
+                if attacker.genome.role == Role::Predator && attacker.beats(defender, &self.fight) {
+                    blobs_to_remove.entry(defender_key).or_insert((defender.pos, DeathCause::Eaten));
+                    self.events.push(SimulationEvent::Fought { winner: attacker_key, loser: defender_key });
+                    kills.push((attacker_key, defender.radius()));
                 }
             }
-            {
-                let dir = (blob2.pos() - blob1.pos()).normalized();
+            if let Some((blob1, blob2)) = self.blobs.get_pair_mut(blob1_key, blob2_key) {
+                let offset = blob2.pos() - blob1.pos();
+                let dir = if offset.length_sqr() > 0. { offset.normalized() } else { Vector2::new(1., 0.) };
                 let pos1 = blob2.pos() - dir * (blob1.radius() + blob2.radius());
                 let pos2 = blob1.pos() + dir * (blob1.radius() + blob2.radius());
-                self.blobs.get_mut(blob1_key).unwrap().set_pos(&mut self.physics, pos1);
-                self.blobs.get_mut(blob2_key).unwrap().set_pos(&mut self.physics, pos2);
+                blob1.set_pos(&mut self.physics, pos1);
+                blob2.set_pos(&mut self.physics, pos2);
+            }
+        }
+
+        if feed_winners {
+            for (winner, loser_radius) in kills {
+                self.blobs[winner].feed(self.fight.nutrition_per_radius * loser_radius);
             }
         }
 
         //  step blobs
         let world = &mut self.physics;
         for (key, blob) in &mut self.blobs {
-            blob.step(&steps[key], timestep, world, self.size);
+            blob.step(&steps[key], timestep, &self.metabolism, &self.environment, self.turning, world, &mut self.rng);
+        }
+        world.integrate(timestep);
+        for blob in self.blobs.values_mut() {
+            blob.follow_circle(world);
+            blob.step_growth(timestep, &self.growth, world);
+        }
+        self.step_walls();
+        self.step_borders();
+
+        //  old scents fade before the blobs leave new ones
+        #[cfg(feature = "scent")]
+        {
+            self.scent.decay(timestep);
+            let amount = self.scent.deposit_rate * timestep;
+            for blob in self.blobs.values() {
+                self.scent.deposit(blob.pos(), amount);
+            }
         }
 
         //  blobs dying
         for (key, blob) in &self.blobs {
-            if blob.hunger > blob.max_hunger {
-                blobs_to_remove.insert(*key, blob.pos());
+            if blob.hunger > blob.genome.max_hunger {
+                blobs_to_remove.entry(*key).or_insert((blob.pos(), DeathCause::Starved));
+            } else if blob.alive_time > blob.genome.max_lifespan {
+                blobs_to_remove.entry(*key).or_insert((blob.pos(), DeathCause::OldAge));
+            }
+        }
+
+        //  blobs reproducing
+        let mut ready = BTreeSet::new();
+        for (key, blob) in &mut self.blobs {
+            if blob.step_reproduction(timestep, &self.reproduction) && !blobs_to_remove.contains_key(key) {
+                ready.insert(*key);
             }
         }
+        let mut children = vec![];
+        let rng = &mut self.rng;
+        let mutation_rate = self.reproduction.mutation_rate;
+        match self.reproduction.mode {
+            ReproductionMode::Asexual => for key in ready {
+                let blob = &mut self.blobs[key];
+                blob.well_fed_time = 0.;
+                let offset = math::unit_vector2(rng) * 2. * blob.radius();
+                let child = BlobBuilder::from(&*blob)
+                    .pos(blob.pos() + offset)
+                    .genome(blob.genome.mutate(rng, mutation_rate));
+                children.push(child.parents(vec![key]));
+            },
+            ReproductionMode::Sexual => for &(blob1_key, blob2_key) in &touching {
+                //  a blob has at most one child in a step
+                if !ready.contains(&blob1_key) || !ready.contains(&blob2_key) { continue; }
+                ready.remove(&blob1_key);
+                ready.remove(&blob2_key);
+                if let Some((blob1, blob2)) = self.blobs.get_pair_mut(blob1_key, blob2_key) {
+                    blob1.well_fed_time = 0.;
+                    blob2.well_fed_time = 0.;
+                    let genome = blob1.genome.crossover(&blob2.genome, rng).mutate(rng, mutation_rate);
+                    let child = BlobBuilder::from(&*blob1)
+                        .pos((blob1.pos() + blob2.pos()) / 2.)
+                        .genome(genome);
+                    children.push(child.parents(vec![blob1_key, blob2_key]));
+                }
+            },
+        }
         
         //  remove
         for food in foods_to_remove {
             self.remove_food(food);
         }
-        for (blob, pos) in blobs_to_remove {
+        let drop_food_of_eaten = self.fight.reward != FightReward::FeedWinner;
+        for (blob, (pos, cause)) in blobs_to_remove {
             self.remove_blob(blob);
+            if cause != DeathCause::Eaten || drop_food_of_eaten {
+                self.insert_food(pos);
+            }
+            self.events.push(SimulationEvent::Died { key: blob, cause });
+        }
+
+        self.despawn_foods(timestep);
+
+        //  add
+        for child in children {
+            let key = self.spawn_blob(child);
+            let parents = self.blobs[key].parents.clone();
+            self.events.push(SimulationEvent::Born { key, parents });
+        }
+        self.spawn_foods(timestep);
+        self.cull();
+
+        self.tick += 1;
+        if self.history.is_some() {
+            let row = HistoryRow::new(self.tick, &self.stats());
+            self.history.as_mut().unwrap().record(row);
+        }
+    }
+
+    /// Remove the least fit blobs, until there are no more than
+    /// the max population.
+    fn cull(&mut self) {
+        if let Some(max_population) = self.population.max_population {
+            self.cull_to(max_population, self.population.fitness);
+        }
+    }
+
+    /// Remove the blobs that are least fit by `fitness`, until no more
+    /// than `target` remain. Each of them dies of being culled.
+    pub fn cull_to(&mut self, target: usize, fitness: impl Fn(&Blob) -> f32) {
+        if self.blobs.len() <= target { return; }
+        let mut blobs: Vec<_> = self.blobs.iter().map(|(&key, blob)| (fitness(blob), key)).collect();
+        //  stable, so equally fit blobs are culled oldest first
+        blobs.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let excess = blobs.len() - target;
+        for &(_, key) in &blobs[..excess] {
+            self.remove_blob(key);
+            self.events.push(SimulationEvent::Died { key, cause: DeathCause::Culled });
+        }
+    }
+
+    /// The objects among the circles a blob's sight touches that are
+    /// inside its POV, with their colors and positions, nearest first.
+    fn seen(&self, blob: &Blob, collided: &[Key<Circle>]) -> Vec<(CircleObject, Color, Vector2)> {
+        let mut seen: Vec<_> = collided.iter()
+            .filter_map(|&key| {
+                let circle = &self.physics.circles[key];
+                let circle_object = self.objects.get(&key).unwrap();
+                let dir = circle.center - blob.pos();
+                //  make sure object inside blob POV 
+                let angle = math::unsigned_angle_vector2(dir, blob.direction).abs();
+                if angle > blob.genome.pov { return None; }
+
+                let color = circle_object.color(self)?;
+                Some((*circle_object, *color, circle.center))
+            })
+            .collect();
+        //  the sort is stable, so equally far objects stay by key
+        seen.sort_by(|(_, _, a), (_, _, b)| {
+            let a = (*a - blob.pos()).length_sqr();
+            let b = (*b - blob.pos()).length_sqr();
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        seen
+    }
+
+    /// Sum the contact normals of the blobs overlapping `blob`,
+    /// each as long as the overlap.
+    fn separation(&self, blob: &Blob, contacts: &physics::CircleContacts) -> Vector2 {
+        let mut sum = Vector2::zero();
+        for contact in contacts.get(&blob.circle).into_iter().flatten() {
+            if let Some(CircleObject::Blob(_)) = self.objects.get(&contact.other) {
+                sum += contact.normal * contact.depth;
+            }
+        }
+        sum
+    }
+
+    /// Advance the simulation by `timestep`, in as many steps as it
+    /// takes for none of them to be longer than `max_substep`.
+    ///
+    /// Long timesteps, like after the window stalls, would otherwise
+    /// let blobs jump past foods and each other.
+    pub fn step_subdivided(&mut self, timestep: f32, max_substep: f32) {
+        debug_assert!(max_substep > 0.);
+        //  the substeps are scaled again in `step`
+        let scaled = timestep * self.time_scale;
+        let count = (scaled / max_substep).ceil().max(1.);
+        for _ in 0..count as usize {
+            self.step(timestep / count);
+        }
+    }
+
+    /// Keep the blobs that left the space in it, by the border mode.
+    fn step_borders(&mut self) {
+        let size = self.size;
+        for circle in self.physics.out_of_bounds() {
+            let blob = match self.objects.get(&circle) {
+                Some(&CircleObject::Blob(blob)) => &mut self.blobs[blob],
+                _ => continue,
+            };
+            let pos = blob.pos();
+            let direction = blob.direction();
+            let clamped = Vector2::new(pos.x.max(0.).min(size.x), pos.y.max(0.).min(size.y));
+            match self.border_mode {
+                BorderMode::Bounce => {
+                    //  the inward normals of the borders the blob left through,
+                    //  so a blob in a corner bounces off both
+                    let normals = [
+                        (pos.x < 0., Vector2::new(1., 0.)),
+                        (pos.x > size.x, Vector2::new(-1., 0.)),
+                        (pos.y < 0., Vector2::new(0., 1.)),
+                        (pos.y > size.y, Vector2::new(0., -1.)),
+                    ];
+                    let mut direction = direction;
+                    for &(crossed, normal) in &normals {
+                        //  only turn back if still going out
+                        if crossed && direction.dot(normal) < 0. {
+                            direction = math::reflect(direction, normal);
+                        }
+                    }
+                    blob.set_pos(&mut self.physics, clamped);
+                    blob.set_direction(&mut self.physics, direction);
+                },
+                BorderMode::Wrap => {
+                    let wrapped = Vector2::new(pos.x.rem_euclid(size.x), pos.y.rem_euclid(size.y));
+                    blob.set_pos(&mut self.physics, wrapped);
+                },
+                BorderMode::Clamp => {
+                    blob.set_pos(&mut self.physics, clamped);
+                },
+            }
+        }
+    }
+
+    /// Push blobs out of the walls they overlap, and turn them away.
+    fn step_walls(&mut self) {
+        let mut touched: Vec<_> = self.physics.aabb_collisions().into_iter().collect();
+        //  in key order, so blobs touching several walls are pushed deterministically
+        touched.sort_by_key(|&(circle, _)| circle);
+        for (circle, aabbs) in touched {
+            let blob = match self.objects.get(&circle) {
+                Some(&CircleObject::Blob(blob)) => &mut self.blobs[blob],
+                _ => continue,
+            };
+            for aabb in aabbs {
+                let aabb = self.physics.aabbs[aabb];
+                if aabb.layer != Self::WALL_LAYER { continue; }
+                let pos = blob.pos();
+                let closest = aabb.closest_point(pos);
+                let offset = pos - closest;
+                let distance = offset.length();
+                //  the direction out of the wall, and the point on its edge to push from
+                let (normal, edge) = if distance > 0. {
+                    (offset / distance, closest)
+                } else {
+                    //  the center is inside, so leave through the nearest side
+                    let sides = [
+                        (pos.x - aabb.min.x, Vector2::new(-1., 0.), Vector2::new(aabb.min.x, pos.y)),
+                        (aabb.max.x - pos.x, Vector2::new(1., 0.), Vector2::new(aabb.max.x, pos.y)),
+                        (pos.y - aabb.min.y, Vector2::new(0., -1.), Vector2::new(pos.x, aabb.min.y)),
+                        (aabb.max.y - pos.y, Vector2::new(0., 1.), Vector2::new(pos.x, aabb.max.y)),
+                    ];
+                    let &(_, normal, edge) = sides.iter()
+                        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+                        .unwrap();
+                    (normal, edge)
+                };
+                blob.set_pos(&mut self.physics, edge + normal * blob.radius());
+                let direction = blob.direction();
+                if direction.dot(normal) < 0. {
+                    blob.set_direction(&mut self.physics, math::reflect(direction, normal));
+                }
+            }
+        }
+    }
+
+    /// Put a wall in the simulation, between the corners `min` and `max`.
+    pub fn insert_wall(&mut self, min: Vector2, max: Vector2) -> Key<physics::Aabb> {
+        self.physics.aabbs.insert(physics::Aabb { min, max, layer: Self::WALL_LAYER })
+    }
+
+    pub fn remove_wall(&mut self, wall: Key<physics::Aabb>) -> bool {
+        self.physics.aabbs.remove(wall).is_some()
+    }
+
+    /// Age the foods by `timestep` seconds, and remove the ones older
+    /// than the food lifetime.
+    fn despawn_foods(&mut self, timestep: f32) {
+        let mut rotten = vec![];
+        for (&key, food) in &mut self.foods {
+            food.age += timestep;
+            if self.food_lifetime.map_or(false, |lifetime| food.age > lifetime) {
+                rotten.push(key);
+            }
+        }
+        for food in rotten {
+            self.remove_food(food);
+            self.events.push(SimulationEvent::FoodDespawned { food });
+        }
+    }
+
+    /// Add the foods the food spawner asks for after `timestep` seconds.
+    fn spawn_foods(&mut self, timestep: f32) {
+        let foods = self.food_spawner.spawn(timestep, self.foods.len(), self.size, &mut self.rng);
+        for pos in foods {
             self.insert_food(pos);
         }
     }
 
-    /// Put a blob in the simulation.
-    pub fn insert_blob(&mut self, 
-        pos: Vector2, radius: f32, color: Color,
-        speed: f32, rotation_speed: f32,
-        pov: f32, sight_depth: f32,
-        favorite_color: Color,
-        color_attraction: f32, color_repulsion: f32,
-        max_hunger: f32,
-        attack: f32, defence: f32,
-        hunger_reduction: f32, hunger_division: f32,
-    ) -> Key<Blob> {
+    /// Put a blob built by `builder` in the simulation.
+    pub fn spawn_blob(&mut self, builder: BlobBuilder) -> Key<Blob> {
+        let BlobBuilder { name, pos, color, genome, parents } = builder;
+        //  parents that were removed do not count toward the generation
+        let generation = parents.iter()
+            .filter_map(|&parent| self.blobs.get(parent))
+            .map(|parent| parent.generation + 1)
+            .max()
+            .unwrap_or(0);
         //  create blob
-        let circle = self.physics.circles.insert(Circle {
-            center: pos, radius: radius, layer: Blob::LAYER,
-        });
-        let sight_circle = self.physics.circles.insert(Circle {
-            center: pos, radius: sight_depth, layer: Blob::SIGHT_LAYER,
-        });
+        let radius = genome.radius;
+        let circle = self.physics.circles.insert(Circle::new(pos, radius, Blob::LAYER));
+        let sight_circle = self.physics.circles.insert(Circle::new_trigger(pos, genome.sight_depth, Blob::SIGHT_LAYER));
         let blob = Blob {
-            name: None,
+            name,
             alive_time: 0.,
-            pos, radius, color,
-            speed, rotation_speed,
-            pov, sight_depth,
-            favorite_color,
-            color_attraction, color_repulsion,
+            genome, color,
+            pos, radius,
             direction: Vector2::zero(),
             circle, sight_circle,
-            max_hunger, hunger: 0.,
-            attack, defence,
-            hunger_reduction, hunger_division,
+            hunger: 0.,
+            well_fed_time: 0.,
+            parents, generation,
         };
         //  insert blob data
         let key = self.blobs.insert(blob);
@@ -282,6 +1180,37 @@ impl Simulation {
 
         key
     }
+
+    /// Put a blob in the simulation.
+    ///
+    /// Prefer `spawn_blob`, which names every argument.
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_blob(&mut self, 
+        pos: Vector2, radius: f32, color: Color,
+        speed: f32, rotation_speed: f32,
+        pov: f32, sight_depth: f32,
+        favorite_color: Color,
+        color_attraction: f32, color_repulsion: f32,
+        max_hunger: f32,
+        attack: f32, defence: f32,
+        hunger_reduction: f32, hunger_division: f32,
+    ) -> Key<Blob> {
+        self.spawn_blob(BlobBuilder {
+            name: None,
+            pos, color,
+            genome: Genome {
+                speed, rotation_speed, radius,
+                pov, sight_depth,
+                favorite_color,
+                color_attraction, color_repulsion,
+                max_hunger,
+                attack, defence,
+                hunger_reduction, hunger_division,
+                ..Genome::default()
+            },
+            parents: vec![],
+        })
+    }
     
     /// Get a blob from the simulation.
     pub fn get_blob(&self, blob: Key<Blob>) -> Option<&Blob> {
@@ -292,6 +1221,48 @@ impl Simulation {
         self.blobs.get_mut(blob)
     }
     
+    pub fn blob_snapshot(&self, blob: Key<Blob>) -> Option<BlobSnapshot> {
+        self.blobs.get(blob).map(Blob::snapshot)
+    }
+
+    /// Find every blob with the given name. Names are not unique.
+    pub fn find_blobs_by_name(&self, name: &str) -> Vec<Key<Blob>> {
+        self.blobs.iter()
+            .filter(|(_, blob)| blob.name.as_deref() == Some(name))
+            .map(|(&key, _)| key)
+            .collect()
+    }
+
+    /// Find the food closest to a blob, seen or not. Returns `None`
+    /// if the blob was removed or there is no food.
+    pub fn nearest_food(&self, blob: Key<Blob>) -> Option<Key<Food>> {
+        let blob = self.blobs.get(blob)?;
+        let circle = self.physics.nearest(blob.pos(), Food::LAYER.into())?;
+        match self.objects.get(&circle) {
+            Some(&CircleObject::Food(food)) => Some(food),
+            _ => None,
+        }
+    }
+
+    /// Find the living descendants of a blob, in the order they were born.
+    ///
+    /// Lineage is followed through the recorded parents, so the
+    /// descendants of a removed blob are only found through its
+    /// living children. The blob itself may have been removed.
+    pub fn descendants(&self, blob: Key<Blob>) -> Vec<Key<Blob>> {
+        let mut lineage = BTreeSet::new();
+        lineage.insert(blob);
+        //  children are always inserted after their parents
+        let mut ret = vec![];
+        for (&key, other) in &self.blobs {
+            if other.parents.iter().any(|parent| lineage.contains(parent)) {
+                lineage.insert(key);
+                ret.push(key);
+            }
+        }
+        ret
+    }
+
     /// Remove a blob from the simulation.
     pub fn remove_blob(&mut self, blob: Key<Blob>) -> Option<Blob> {
         //  try remove blob
@@ -307,19 +1278,45 @@ impl Simulation {
         blob
     }
 
+    /// Move a blob into another simulation, keeping its state, and
+    /// return its key there.
+    ///
+    /// The blob keeps its position relative to the space. Its parents
+    /// are forgotten, since their keys mean nothing in `other`.
+    pub fn migrate_to(&mut self, other: &mut Simulation, blob: Key<Blob>) -> Option<Key<Blob>> {
+        let mut blob = self.remove_blob(blob)?;
+        blob.pos = blob.pos / self.size * other.size;
+        blob.parents.clear();
+        //  register the blob's circles in the other physics world
+        blob.circle = other.physics.circles.insert(Circle::new(blob.pos, blob.radius, Blob::LAYER));
+        blob.sight_circle = other.physics.circles.insert(Circle::new_trigger(blob.pos, blob.genome.sight_depth, Blob::SIGHT_LAYER));
+        blob.set_direction(&mut other.physics, blob.direction);
+        let (circle, sight_circle) = (blob.circle, blob.sight_circle);
+        let key = other.blobs.insert(blob);
+        other.objects.insert(circle, CircleObject::Blob(key));
+        other.objects.insert(sight_circle, CircleObject::BlobSight(key));
+
+        Some(key)
+    }
+
     pub fn set_blob_pos(&mut self, blob: Key<Blob>, pos: Vector2) {
         if let Some(blob) = self.blobs.get_mut(blob) {
             blob.set_pos(&mut self.physics, pos);
         }
     }
 
-    /// Put a food in the simulation.
+    /// Put a regular food in the simulation.
     pub fn insert_food(&mut self, pos: Vector2) -> Key<Food> {
+        self.insert_food_with_nutrition(pos, Food::NUTRITION)
+    }
+
+    /// Put a food in the simulation that is worth more or less than
+    /// a regular food.
+    pub fn insert_food_with_nutrition(&mut self, pos: Vector2, nutrition: f32) -> Key<Food> {
+        debug_assert!(nutrition >= 0.);
         //  create food
-        let circle = self.physics.circles.insert(Circle {
-            center: pos, radius: Food::RADIUS, layer: Food::LAYER,
-        });
-        let food = Food { pos, circle };
+        let circle = self.physics.circles.insert(Circle::new_static(pos, Food::RADIUS, Food::LAYER));
+        let food = Food { pos, circle, nutrition, age: 0. };
         //  insert data
         let key = self.foods.insert(food);
         self.objects.insert(circle, CircleObject::Food(key));
@@ -349,33 +1346,166 @@ impl Simulation {
         food
     }
 
-    pub fn select(&mut self, pos: Vector2) -> (Vec<Key<Blob>>, Vec<Key<Food>>) {
+    /// Remove every food from the simulation, keeping the blobs.
+    pub fn clear_foods(&mut self) {
+        for food in self.foods.values() {
+            self.objects.remove(&food.circle);
+            self.physics.circles.remove(food.circle);
+        }
+        self.foods.clear();
+    }
+
+    pub fn blob_count(&self) -> usize { self.blobs.len() }
+
+    pub fn food_count(&self) -> usize { self.foods.len() }
+
+    /// Take the events that happened since they were last taken,
+    /// in the order they happened.
+    ///
+    /// Events pile up until they are taken.
+    pub fn take_events(&mut self) -> Vec<SimulationEvent> {
+        std::mem::take(&mut self.events.events)
+    }
+
+    /// Call `f` with every event as it happens, in the order the
+    /// callbacks were registered.
+    ///
+    /// The callbacks run in the middle of `step`, while the simulation
+    /// is mutably borrowed, so they cannot reach back into it. Events
+    /// are still kept for `take_events`.
+    pub fn on_event(&mut self, f: EventObserver) {
+        self.events.observers.push(f);
+    }
+
+    /// Compute statistics of the current population.
+    pub fn stats(&self) -> Stats {
+        let genes = |gene: fn(&Genome) -> f32| TraitStats::new(self.blobs.values().map(|blob| gene(&blob.genome)));
+        let mean_alive_time = TraitStats::new(self.blobs.values().map(|blob| blob.alive_time)).mean;
+        let max_alive_time = self.blobs.iter()
+            .map(|(&key, blob)| (key, blob.alive_time))
+            .fold(None, |oldest: Option<(Key<Blob>, f32)>, (key, alive_time)| match oldest {
+                Some((_, max)) if max >= alive_time => oldest,
+                _ => Some((key, alive_time)),
+            });
+        Stats {
+            blob_count: self.blobs.len(),
+            food_count: self.foods.len(),
+            speed: genes(|genome| genome.speed),
+            pov: genes(|genome| genome.pov),
+            sight_depth: genes(|genome| genome.sight_depth),
+            attack: genes(|genome| genome.attack),
+            defence: genes(|genome| genome.defence),
+            mean_alive_time,
+            max_alive_time,
+        }
+    }
+
+    /// Find the blobs and foods at a point.
+    pub fn select(&self, pos: Vector2) -> (Vec<Key<Blob>>, Vec<Key<Food>>) {
+        let mask = self.physics.layer_mask(Self::SELECTION_LAYER);
+        self.selected(self.physics.query_point(pos, mask))
+    }
+
+    /// Find the blobs and foods whose centers are in the rectangle
+    /// between the corners `min` and `max`, like a box selection.
+    pub fn select_rect(&self, min: Vector2, max: Vector2) -> (Vec<Key<Blob>>, Vec<Key<Food>>) {
+        self.query_region(min, max)
+    }
+
+    /// Find the blobs and foods whose centers are in the rectangle
+    /// between the corners `min` and `max`.
+    ///
+    /// Queries do not put anything in the physics world, so there is
+    /// nothing to clean up after them.
+    pub fn query_region(&self, min: Vector2, max: Vector2) -> (Vec<Key<Blob>>, Vec<Key<Food>>) {
+        let mask = self.physics.layer_mask(Self::SELECTION_LAYER);
+        let aabb = physics::Aabb { min, max, layer: Self::SELECTION_LAYER };
+        self.selected(self.physics.query_aabb(&aabb, mask))
+    }
+
+    /// Split selected circles into blobs and foods.
+    fn selected(&self, circles: Vec<Key<Circle>>) -> (Vec<Key<Blob>>, Vec<Key<Food>>) {
         let mut foods = vec![];
         let mut blobs = vec![];
-        let key = self.physics.circles.insert(Circle {
-            center: pos, 
-            radius: 0.01,
-            layer: Self::SELECTION_LAYER,
-        });
-        let collisions = self.physics.collisions();
-        self.physics.circles.remove(key);
-        if let Some(collided) = collisions.get(&key) {
-            for touched in collided {
-                match self.objects.get(touched) {
-                    Some(&CircleObject::Blob(blob)) => blobs.push(blob),
-                    Some(&CircleObject::Food(food)) => foods.push(food),
-                    _ => (),
-                }
+        for touched in circles {
+            match self.objects.get(&touched) {
+                Some(&CircleObject::Blob(blob)) => blobs.push(blob),
+                Some(&CircleObject::Food(food)) => foods.push(food),
+                _ => (),
             }
-            (blobs, foods)
-        } else {
-            (vec![], vec![])
+        }
+        (blobs, foods)
+    }
+}
+
+impl Default for BlobBuilder {
+    fn default() -> Self {
+        Self {
+            name: None,
+            pos: Vector2::zero(),
+            color: Color::GRAY,
+            genome: Genome::default(),
+            parents: vec![],
         }
     }
 }
 
+/// A builder for a blob with the same properties as an existing one.
+impl From<&Blob> for BlobBuilder {
+    fn from(blob: &Blob) -> Self {
+        Self {
+            name: blob.name.clone(),
+            pos: blob.pos,
+            color: blob.color,
+            genome: blob.genome.clone(),
+            parents: vec![],
+        }
+    }
+}
+
+impl BlobBuilder {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn name(mut self, value: impl Into<String>) -> Self { self.name = Some(value.into()); self }
+    pub fn pos(mut self, value: Vector2) -> Self { self.pos = value; self }
+    pub fn color(mut self, value: Color) -> Self { self.color = value; self }
+    pub fn genome(mut self, value: Genome) -> Self { self.genome = value; self }
+    pub fn parents(mut self, value: Vec<Key<Blob>>) -> Self { self.parents = value; self }
+    pub fn radius(mut self, value: f32) -> Self { self.genome.radius = value; self }
+    pub fn speed(mut self, value: f32) -> Self { self.genome.speed = value; self }
+    pub fn rotation_speed(mut self, value: f32) -> Self { self.genome.rotation_speed = value; self }
+    pub fn pov(mut self, value: f32) -> Self { self.genome.pov = value; self }
+    pub fn sight_depth(mut self, value: f32) -> Self { self.genome.sight_depth = value; self }
+    pub fn favorite_color(mut self, value: Color) -> Self { self.genome.favorite_color = value; self }
+    pub fn color_attraction(mut self, value: f32) -> Self { self.genome.color_attraction = value; self }
+    pub fn steering(mut self, value: Steering) -> Self { self.genome.steering = value; self }
+    pub fn role(mut self, value: Role) -> Self { self.genome.role = value; self }
+    pub fn color_repulsion(mut self, value: f32) -> Self { self.genome.color_repulsion = value; self }
+    pub fn max_hunger(mut self, value: f32) -> Self { self.genome.max_hunger = value; self }
+    pub fn hunger_rate(mut self, value: f32) -> Self { self.genome.hunger_rate = value; self }
+    pub fn attack(mut self, value: f32) -> Self { self.genome.attack = value; self }
+    pub fn defence(mut self, value: f32) -> Self { self.genome.defence = value; self }
+    pub fn hunger_reduction(mut self, value: f32) -> Self { self.genome.hunger_reduction = value; self }
+    pub fn hunger_division(mut self, value: f32) -> Self { self.genome.hunger_division = value; self }
+    pub fn max_radius(mut self, value: f32) -> Self { self.genome.max_radius = value; self }
+    pub fn max_lifespan(mut self, value: f32) -> Self { self.genome.max_lifespan = value; self }
+}
+
+/// Where a blob goes in a step.
 pub struct BlobStep {
     target_direction: Option<Vector2>,
+    /// Away from the blobs overlapping this one, longer as they
+    /// overlap more.
+    separation: Vector2,
+    /// Up the scent gradient. Zero without scent trails.
+    scent: Vector2,
+}
+
+impl BlobStep {
+    /// Turn toward `target_direction`, or keep going straight.
+    pub fn new(target_direction: Option<Vector2>) -> Self {
+        Self { target_direction, separation: Vector2::zero(), scent: Vector2::zero() }
+    }
 }
 
 impl Blob {
@@ -386,55 +1516,110 @@ impl Blob {
 
     pub fn set_pos(&mut self, world: &mut physics::World, value: Vector2) {
         self.pos = value;
-        world.circles.get_mut(self.circle).unwrap().center = value;
-        world.circles.get_mut(self.sight_circle).unwrap().center = value;
+        world.circles[self.circle].center = value;
+        world.circles[self.sight_circle].center = value;
+    }
+
+    pub fn genome(&self) -> &Genome { &self.genome }
+
+    pub fn snapshot(&self) -> BlobSnapshot {
+        let genome = &self.genome;
+        BlobSnapshot {
+            pos: self.pos,
+            direction: self.direction,
+            radius: self.radius,
+            max_radius: genome.max_radius,
+            color: self.color,
+            favorite_color: genome.favorite_color,
+            speed: genome.speed,
+            rotation_speed: genome.rotation_speed,
+            pov: genome.pov,
+            sight_depth: genome.sight_depth,
+            color_attraction: genome.color_attraction,
+            color_repulsion: genome.color_repulsion,
+            steering: genome.steering,
+            role: genome.role,
+            hunger: self.hunger,
+            max_hunger: genome.max_hunger,
+            hunger_rate: genome.hunger_rate,
+            hunger_ratio: self.hunger / genome.max_hunger,
+            attack: genome.attack,
+            defence: genome.defence,
+            hunger_reduction: genome.hunger_reduction,
+            hunger_division: genome.hunger_division,
+            alive_time: self.alive_time,
+            max_lifespan: genome.max_lifespan,
+            well_fed_time: self.well_fed_time,
+            generation: self.generation,
+        }
     }
 
     pub fn radius(&self) -> f32 { self.radius }
 
     pub fn set_radius(&mut self, world: &mut physics::World, value: f32) {
         self.radius = value;
-        world.circles.get_mut(self.circle).unwrap().radius = value;    
+        world.circles[self.circle].radius = value;    
     }
 
     pub fn direction(&self) -> Vector2 { self.direction }
 
-    pub fn set_direction(&mut self, _world: &mut physics::World, value: Vector2) {
-        self.direction = value;
+    pub fn set_direction(&mut self, world: &mut physics::World, value: Vector2) {
+        self.set_velocity(world, value, self.genome.speed);
+    }
+
+    fn set_velocity(&mut self, world: &mut physics::World, direction: Vector2, speed: f32) {
+        self.direction = direction;
+        //  the sight circle moves with the blob
+        let velocity = self.direction * speed;
+        world.circles[self.circle].velocity = velocity;
+        world.circles[self.sight_circle].velocity = velocity;
+    }
+
+    /// How fast the blob moves in an environment.
+    pub fn effective_speed(&self, environment: &Environment) -> f32 {
+        self.genome.speed * environment.speed_factor()
+    }
+
+    /// How much hunger the blob gains every second in an environment,
+    /// its metabolic rate scaled by the heat.
+    pub fn effective_metabolic_rate(&self, config: &MetabolismConfig, environment: &Environment) -> f32 {
+        self.metabolic_rate(config) * environment.hunger_factor()
     }
 
-    pub fn sight_depth(&self) -> f32 { self.sight_depth }
+    pub fn sight_depth(&self) -> f32 { self.genome.sight_depth }
 
     pub fn set_sight_depth(&mut self, world: &mut physics::World, value: f32) {
-        self.sight_depth = value;
-        world.circles.get_mut(self.sight_circle).unwrap().radius = value;
+        self.genome.sight_depth = value;
+        world.circles[self.sight_circle].radius = value;
     }
 
+    #[cfg(feature = "render")]
     fn fade_color(&self, color: &Color) -> Color {
-        color.fade(1. - self.hunger / self.max_hunger)
+        color.fade(1. - self.hunger / self.genome.max_hunger)
     }
 
-    pub fn feed(&mut self) { 
-        //  h1 = max( (h0 - hunger_reduction*h_max) / (1 + hunger_division),  0 )
+    pub fn feed(&mut self, nutrition: f32) { 
+        //  h1 = max( (h0 - nutrition*hunger_reduction*h_max) / (1 + hunger_division),  0 )
         self.hunger = f32::max(
-            (self.hunger - self.hunger_reduction * self.max_hunger)
+            (self.hunger - nutrition * self.genome.hunger_reduction * self.genome.max_hunger)
             /
-            (1. + self.hunger_division),
+            (1. + self.genome.hunger_division),
             0.
         );
     }
 
-    pub fn draw(&self, draw: &mut DrawingContext) {
+    #[cfg(feature = "render")]
+    pub fn draw<D: RaylibDraw>(&self, draw: &mut D, show_sight: bool) {
 
         const FONT_HEIGHT: i32 = 20;
 
-        draw.draw_circle_v(self.pos, self.radius, self.fade_color(&self.color));
+        draw.draw_circle_v(self.pos, self.radius(), self.fade_color(&self.color));
         
         if let Some(name) = &self.name {
             draw.draw_text(name,
                 (self.pos().x - self.radius()) as i32,
                 (self.pos().y - self.radius() - 2. * FONT_HEIGHT as f32) as i32,
-                FONT_HEIGHT, self.fade_color(&self.favorite_color),
+                FONT_HEIGHT, self.fade_color(&self.genome.favorite_color),
             );
         }
 
@@ -442,100 +1627,186 @@ impl Blob {
         draw.draw_text(&format!("{:.1}", self.alive_time),
             (self.pos().x - self.radius()) as i32,
             (self.pos().y - self.radius() - FONT_HEIGHT as f32) as i32,
-            FONT_HEIGHT, self.fade_color(&self.favorite_color),
+            FONT_HEIGHT, self.fade_color(&self.genome.favorite_color),
         );
 
-        // //  sight drawing
-        // let angle = self.direction.x.atan2(self.direction.y).to_degrees();
-        // draw.draw_circle_sector_lines(
-        //     self.pos,                       //  start
-        //     self.sight_depth,               //  radius
-        //     (angle - self.pov / 2.) as i32, //  start_angle
-        //     (angle + self.pov / 2.) as i32, //  end_angle
-        //     25,                             //  segments
-        //     self.favorite_color,            //  color
-        // );
-        // draw.draw_line_v(self.pos, self.pos + self.direction * 3. * self.speed, self.favorite_color);
+        //  sight drawing
+        if show_sight {
+            //  raylib's angles start at the x axis, and turn toward the y axis
+            let angle = self.direction.y.atan2(self.direction.x).to_degrees();
+            //  like in `Simulation::seen`, objects up to `pov` degrees
+            //  to either side are seen
+            draw.draw_circle_sector_lines(
+                self.pos,                               //  start
+                self.genome.sight_depth,                //  radius
+                (angle - self.genome.pov) as i32,       //  start_angle
+                (angle + self.genome.pov) as i32,       //  end_angle
+                25,                                     //  segments
+                self.genome.favorite_color,             //  color
+            );
+            //  where the blob will be in a second
+            draw.draw_line_v(self.pos, self.pos + self.direction * self.genome.speed, self.genome.favorite_color);
+        }
     }
 
-    pub fn prepare_step<'a, I>(&self, seen: I) -> BlobStep
-    where I: std::iter::IntoIterator<Item=(&'a CircleObject, &'a Color, &'a Vector2)> {
-
-        let mut sum = Vector2::zero();
-        let mut count = 0.;
-        for (_, color, pos) in seen {
-
-            let v = color_similarity(&self.favorite_color, color);
-            let v = v * (if v > 0. { self.color_attraction } else { self.color_repulsion });
-            
-            if (*pos - self.pos).length_sqr() != 0. {
-                let target_dir = (*pos - self.pos).normalized();
-                sum += target_dir * v; 
-                count += v.abs();
+    /// Sense what the blob sees, as scalars that do not depend on
+    /// how many things it sees. Things at the blob's own center, like
+    /// the blob itself, only count toward the seen list.
+    pub fn sense<'a>(&'a self, seen: &'a [(CircleObject, Color, Vector2)]) -> Perception<'a> {
+        let mut nearest_food: Option<Sensed> = None;
+        let mut nearest_blob: Option<Sensed> = None;
+        let mut similarity = 0.;
+        let mut count = 0;
+        for &(object, color, pos) in seen {
+            let offset = pos - self.pos;
+            if offset.length_sqr() == 0. { continue; }
+            similarity += color_similarity(&self.genome.favorite_color, &color);
+            count += 1;
+
+            let distance = offset.length();
+            let sensed = Sensed {
+                offset,
+                distance: if self.genome.sight_depth > 0. { (distance / self.genome.sight_depth).min(1.) } else { 1. },
+                angle: math::signed_angle_vector2(self.direction, offset) / 180.,
+            };
+            let nearest = match object {
+                CircleObject::Food(_) => &mut nearest_food,
+                CircleObject::Blob(_) => &mut nearest_blob,
+                CircleObject::BlobSight(_) => continue,
+            };
+            if nearest.map_or(true, |nearest| distance < nearest.offset.length()) {
+                *nearest = Some(sensed);
             }
         }
-        
-        let target_direction = if count == 0. || sum.length_sqr() == 0. {
-            None
-        } else {
-            let d = (sum / count as f32).normalized();
-            Some(d)
-        };
+        Perception {
+            blob: self,
+            seen,
+            hunger_ratio: self.hunger / self.genome.max_hunger,
+            nearest_food,
+            nearest_blob,
+            color_similarity: if count == 0 { 0. } else { similarity / count as f32 },
+        }
+    }
 
-        BlobStep { target_direction }
+    /// Choose where to go from what the blob sees, by the steering
+    /// its genome names, with nearer things counting more by `falloff`
+    /// when it steers by color.
+    pub fn prepare_step(&self, seen: &[(CircleObject, Color, Vector2)], falloff: f32) -> BlobStep {
+        let perception = self.sense(seen);
+        let color = ColorSteering { falloff };
+        match self.genome.steering {
+            Steering::ColorAverage => color.decide(&perception),
+            Steering::NearestFood => NearestFoodSteering { fallback: color }.decide(&perception),
+            Steering::Neural => NeuralSteering.decide(&perception),
+        }
     }
 
-    pub fn step(&mut self, step: &BlobStep, timestep: f32, physics_world: &mut physics::World, world_size: Vector2) {
+    pub fn step<R: Rng + ?Sized>(&mut self, step: &BlobStep, timestep: f32, metabolism: &MetabolismConfig, environment: &Environment, turning: Turning, physics_world: &mut physics::World, rng: &mut R) {
         
         //  update direction
         if self.direction == Vector2::zero() {
-            self.direction = random_vector2() * 2. - 1.;
+            self.direction = math::unit_vector2(rng);
         }
         else if let Some(target_direction) = step.target_direction {
-            let t = self.rotation_speed * timestep;
-            self.direction = math::slerp(self.direction, target_direction, t);
+            let t = self.genome.rotation_speed * timestep;
+            self.direction = match turning {
+                Turning::Slerp => math::slerp(self.direction, target_direction, t),
+                Turning::ConstantSpeed => math::move_towards(self.direction, target_direction, t.to_degrees()),
+            };
         } 
+        //  turn away from overlapping blobs, and toward stronger scents
+        let push = step.separation + step.scent;
+        if push != Vector2::zero() {
+            let direction = self.direction + push;
+            if direction != Vector2::zero() {
+                self.direction = direction.normalized();
+            }
+        }
 
-        //  move position
-        self.pos += self.direction * self.speed * timestep;
-        physics_world.circles.get_mut(self.circle).unwrap().center = self.pos;
-        physics_world.circles.get_mut(self.sight_circle).unwrap().center = self.pos;
+        //  move position, when the physics world integrates
+        self.set_velocity(physics_world, self.direction, self.effective_speed(environment));
         
         //  do hunger
-        self.hunger += timestep;
+        self.hunger += self.effective_metabolic_rate(metabolism, environment) * timestep;
 
-        //  do border
-        if self.pos().x > world_size.x {
-            self.set_pos(physics_world, Vector2::new(world_size.x, self.pos().y));
-            self.set_direction(physics_world, Vector2::new(-self.direction().x, self.direction().y));
-        }
-        if self.pos().y > world_size.y {
-            self.set_pos(physics_world, Vector2::new(self.pos().x, world_size.y));
-            self.set_direction(physics_world, Vector2::new(self.direction().x, -self.direction().y));
+        //  do time
+        self.alive_time += timestep;
+    }
+
+    /// How much hunger the blob gains every second, scaled by its
+    /// hunger rate.
+    pub fn metabolic_rate(&self, config: &MetabolismConfig) -> f32 {
+        let speed = self.genome.speed;
+        let radius = self.radius;
+        let rate = config.base_rate + config.speed_cost * speed * speed + config.size_cost * radius * radius;
+        rate * self.genome.hunger_rate
+    }
+
+    /// The default fitness: how far the blob is from starving or
+    /// dying of old age, whichever is closer, from 1 to 0.
+    pub fn fitness(&self) -> f32 {
+        let fed = 1. - self.hunger / self.genome.max_hunger;
+        let young = 1. - self.alive_time / self.genome.max_lifespan;
+        fed.min(young)
+    }
+
+    /// Returns true if the blob kills `defender` when they fight.
+    ///
+    /// The blob's attack, weighted by its size relative to the
+    /// defender's, has to be greater than the defender's defence,
+    /// which weakens as the defender gets hungry.
+    pub fn beats(&self, defender: &Blob, config: &FightConfig) -> bool {
+        let size_advantage = (self.radius / defender.radius).powf(config.size_weight);
+        let attack = self.genome.attack * size_advantage;
+        let defence = defender.genome.defence * (1. - defender.hunger / defender.genome.max_hunger);
+        attack > defence
+    }
+
+    /// Grow the radius by `amount`, up to the max radius.
+    pub fn grow(&mut self, world: &mut physics::World, amount: f32) {
+        if self.radius < self.genome.max_radius {
+            self.set_radius(world, (self.radius + amount).min(self.genome.max_radius));
         }
-        if self.pos().x < 0. {
-            self.set_pos(physics_world, Vector2::new(0., self.pos().y));
-            self.set_direction(physics_world, Vector2::new(-self.direction().x, self.direction().y));
+    }
+
+    /// Shrink the radius while the blob is starving.
+    pub fn step_growth(&mut self, timestep: f32, config: &GrowthConfig, world: &mut physics::World) {
+        if self.hunger > config.starving_threshold * self.genome.max_hunger {
+            let radius = (self.radius - config.shrink_rate * timestep).max(Genome::MIN_RADIUS);
+            self.set_radius(world, radius);
         }
-        if self.pos().y < 0. {
-            self.set_pos(physics_world, Vector2::new(self.pos().x, 0.));
-            self.set_direction(physics_world, Vector2::new(self.direction().x, -self.direction().y));
+    }
+
+    /// Count the time the blob is well-fed. Returns true when the blob
+    /// has been well-fed long enough to reproduce.
+    pub fn step_reproduction(&mut self, timestep: f32, config: &ReproductionConfig) -> bool {
+        if self.hunger < config.hunger_threshold * self.genome.max_hunger {
+            self.well_fed_time += timestep;
+        } else {
+            self.well_fed_time = 0.;
         }
+        self.well_fed_time >= config.well_fed_time
+    }
 
-        //  do time
-        self.alive_time += timestep;
+    /// Follow the blob's circle after the physics world integrated.
+    pub fn follow_circle(&mut self, physics_world: &physics::World) {
+        self.pos = physics_world.circles[self.circle].center;
     }
 }
 
 impl Food {
     pub const LAYER: physics::Layer = physics::Layer::new(2);
     pub const COLOR: Color = Color::GREEN;
+    /// The color of foods worth twice a regular food, or more.
+    pub const RICH_COLOR: Color = Color::GOLD;
     pub const RADIUS: f32 = 5.;
+    /// The nutrition of a regular food.
+    pub const NUTRITION: f32 = 1.;
 
     pub fn pos(&self) -> Vector2 { self.pos }
 
     fn circle_mut<'a>(&self, physics_world: &'a mut physics::World) -> &'a mut Circle {
-        physics_world.circles.get_mut(self.circle).unwrap()
+        &mut physics_world.circles[self.circle]
     }
 
     pub fn set_pos(&mut self, physics_world: &mut physics::World, value: Vector2) {
@@ -543,8 +1814,27 @@ impl Food {
         self.circle_mut(physics_world).center = value;
     }
 
-    pub fn draw(&self, draw: &mut DrawingContext) {
-        draw.draw_circle_v(self.pos, Self::RADIUS, Self::COLOR);
+    /// The color the food is drawn with. Richer foods are more golden,
+    /// and poorer foods are paler.
+    #[cfg(feature = "render")]
+    pub fn draw_color(&self) -> Color {
+        let richness = self.nutrition / Self::NUTRITION;
+        if richness < 1. {
+            return Self::COLOR.fade(richness.max(0.2));
+        }
+        let t = (richness - 1.).min(1.);
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+        Color::new(
+            channel(Self::COLOR.r, Self::RICH_COLOR.r),
+            channel(Self::COLOR.g, Self::RICH_COLOR.g),
+            channel(Self::COLOR.b, Self::RICH_COLOR.b),
+            255,
+        )
+    }
+
+    #[cfg(feature = "render")]
+    pub fn draw<D: RaylibDraw>(&self, draw: &mut D) {
+        draw.draw_circle_v(self.pos, Self::RADIUS, self.draw_color());
     }
 }
 
@@ -558,6 +1848,966 @@ impl CircleObject {
     }
 }
 
+#[cfg(feature = "save")]
+mod save {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::*;
+
+    pub fn default_fitness() -> fn(&Blob) -> f32 { Blob::fitness }
+
+    //  JSON maps need string keys, so the objects are saved as
+    //  pairs, in key order
+    pub mod objects {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(objects: &HashMap<Key<Circle>, CircleObject>, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut pairs: Vec<_> = objects.iter().collect();
+            pairs.sort_by_key(|&(&key, _)| key);
+            serializer.collect_seq(pairs)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<Key<Circle>, CircleObject>, D::Error> {
+            let pairs = Vec::<(Key<Circle>, CircleObject)>::deserialize(deserializer)?;
+            Ok(pairs.into_iter().collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::replay::prelude::*;
+
+    #[test]
+    fn test_eaten_food_leaves_physics() {
+        let mut sim = Simulation::new(Vector2::new(100., 100.));
+        sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(50., 50.)));
+        let food = sim.insert_food(Vector2::new(50., 50.));
+        let circles = sim.physics.circles.len();
+
+        sim.step(0.01);
+        assert!(sim.get_food(food).is_none());
+        assert_eq!(sim.physics.circles.len(), circles - 1);
+        assert_eq!(sim.objects.len(), circles - 1);
+    }
+
+    #[test]
+    fn test_rich_food_feeds_more() {
+        let mut sim = Simulation::new(Vector2::new(200., 200.));
+        let rich = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(50., 50.)));
+        let poor = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(150., 150.)));
+        sim.get_blob_mut(rich).unwrap().hunger = 10.;
+        sim.get_blob_mut(poor).unwrap().hunger = 10.;
+        sim.insert_food_with_nutrition(Vector2::new(50., 50.), 2.);
+        sim.insert_food_with_nutrition(Vector2::new(150., 150.), 0.5);
+
+        sim.step(0.01);
+        let rich = sim.get_blob(rich).unwrap().hunger;
+        let poor = sim.get_blob(poor).unwrap().hunger;
+        assert!(rich < poor && poor < 10.);
+    }
+
+    #[test]
+    fn test_blob_grows_when_eating() {
+        let mut sim = Simulation::new(Vector2::new(100., 100.));
+        let key = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(50., 50.)).radius(10.).max_radius(20.));
+        sim.insert_food(Vector2::new(50., 50.));
+
+        sim.step(0.01);
+        let blob = sim.get_blob(key).unwrap();
+        assert_eq!(blob.radius(), 10. + sim.growth.growth);
+        assert_eq!(sim.physics.circles[blob.circle].radius, blob.radius());
+    }
+
+    #[test]
+    fn test_starving_blob_shrinks() {
+        let mut sim = Simulation::new(Vector2::new(100., 100.));
+        let key = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(50., 50.)).radius(10.));
+        sim.get_blob_mut(key).unwrap().hunger = 10.;
+
+        sim.step(0.5);
+        let blob = sim.get_blob(key).unwrap();
+        assert!(blob.radius() < 10.);
+        assert_eq!(sim.physics.circles[blob.circle].radius, blob.radius());
+    }
+
+    #[test]
+    fn test_coincident_blobs_separate() {
+        //  the directions of two coincident blobs heading the same way
+        //  after a step
+        let directions = |separation_weight| {
+            let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+            sim.separation_weight = separation_weight;
+            let blob = || BlobBuilder::new().pos(Vector2::new(100., 100.)).attack(0.).sight_depth(0.);
+            let a = sim.spawn_blob(blob());
+            let b = sim.spawn_blob(blob());
+            for &key in &[a, b] {
+                sim.get_blob_mut(key).unwrap().direction = Vector2::new(0., 1.);
+            }
+            sim.step(0.05);
+            (sim.get_blob(a).unwrap().direction, sim.get_blob(b).unwrap().direction)
+        };
+
+        let (a, b) = directions(0.);
+        assert_eq!((a, b), (Vector2::new(0., 1.), Vector2::new(0., 1.)));
+        //  the blobs turn away from each other, along opposite normals
+        let (a, b) = directions(0.1);
+        assert!(a.x > 0. && b.x < 0.);
+        assert!((a.x + b.x).abs() < 1e-6);
+        assert!((a.y - b.y).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_large_blob_wins_fight() {
+        let fight = |size_weight| {
+            let mut sim = Simulation::new_seeded(Vector2::new(100., 100.), 7);
+            sim.fight.size_weight = size_weight;
+            let large = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(40., 50.)).radius(20.).attack(0.5).defence(1.));
+            let small = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(62., 50.)).radius(5.).attack(1.5).defence(1.));
+            sim.step(0.01);
+            let fights: Vec<_> = sim.take_events().into_iter()
+                .filter_map(|event| match event {
+                    SimulationEvent::Fought { winner, loser } => Some((winner, loser)),
+                    _ => None,
+                })
+                .collect();
+            (large, small, fights)
+        };
+
+        //  the small blob wins when size does not matter
+        let (large, small, fights) = fight(0.);
+        assert_eq!(fights, vec![(small, large)]);
+        let (large, small, fights) = fight(1.);
+        assert_eq!(fights, vec![(large, small)]);
+    }
+
+    #[test]
+    fn test_only_predators_attack() {
+        let fights = |role| {
+            let mut sim = Simulation::new_seeded(Vector2::new(100., 100.), 7);
+            let blob = |x| BlobBuilder::new().pos(Vector2::new(x, 50.)).attack(2.).defence(0.5);
+            let attacker = sim.spawn_blob(blob(40.).role(role));
+            let prey = sim.spawn_blob(blob(55.).role(Role::Prey));
+            sim.step(0.01);
+            let fights: Vec<_> = sim.take_events().into_iter()
+                .filter_map(|event| match event {
+                    SimulationEvent::Fought { winner, loser } => Some((winner, loser)),
+                    _ => None,
+                })
+                .collect();
+            (attacker, prey, fights)
+        };
+
+        let (_, _, prey_fights) = fights(Role::Prey);
+        assert_eq!(prey_fights, vec![]);
+        let (predator, prey, predator_fights) = fights(Role::Predator);
+        assert_eq!(predator_fights, vec![(predator, prey)]);
+    }
+
+    #[test]
+    fn test_winner_eats_loser() {
+        let mut sim = Simulation::new_seeded(Vector2::new(100., 100.), 7);
+        sim.fight.reward = FightReward::FeedWinner;
+        let winner = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(40., 50.)).attack(2.));
+        sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(55., 50.)).attack(0.));
+        sim.get_blob_mut(winner).unwrap().hunger = 10.;
+
+        sim.step(0.01);
+        assert_eq!(sim.blobs.len(), 1);
+        assert!(sim.get_blob(winner).unwrap().hunger < 10.);
+        //  the loser was eaten whole
+        assert_eq!(sim.foods.len(), 0);
+    }
+
+    #[test]
+    fn test_nearest_food_steering() {
+        let steer = |steering| {
+            let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+            let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)).pov(180.).steering(steering));
+            sim.get_blob_mut(blob).unwrap().direction = Vector2::new(1., 0.);
+            //  the nearest food is below, but most of the food is above
+            sim.insert_food(Vector2::new(130., 110.));
+            sim.insert_food(Vector2::new(160., 60.));
+            sim.insert_food(Vector2::new(165., 55.));
+            sim.step(0.1);
+            sim.get_blob(blob).unwrap().direction()
+        };
+
+        assert!(steer(Steering::NearestFood).y > 0.);
+        assert!(steer(Steering::ColorAverage).y < 0.);
+    }
+
+    #[test]
+    fn test_simulation_steering_overrides_genome() {
+        struct Down;
+
+        impl SteeringStrategy for Down {
+            fn decide(&self, _: &Perception) -> BlobStep {
+                BlobStep::new(Some(Vector2::new(0., 1.)))
+            }
+        }
+
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 400.), 7);
+        sim.steering = Some(Box::new(Down));
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)).pov(180.).steering(Steering::NearestFood));
+        sim.get_blob_mut(blob).unwrap().direction = Vector2::new(1., 0.);
+        //  food straight ahead, which the genome's steering would go to
+        sim.insert_food(Vector2::new(130., 100.));
+
+        sim.step(0.1);
+        assert!(sim.get_blob(blob).unwrap().direction().y > 0.);
+        for _ in 0..20 { sim.step(0.1); }
+        let direction = sim.get_blob(blob).unwrap().direction();
+        assert!((direction - Vector2::new(0., 1.)).length() < 1e-2, "{:?}", direction);
+    }
+
+    #[test]
+    fn test_nearer_food_steers_more() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)).sight_depth(50.).pov(90.)
+            .favorite_color(Food::COLOR).steering(Steering::ColorAverage));
+        let far = sim.insert_food(Vector2::new(130., 130.));
+        let near = sim.insert_food(Vector2::new(110., 90.));
+        sim.get_blob_mut(blob).unwrap().direction = Vector2::new(1., 0.);
+
+        let blob_ref = sim.get_blob(blob).unwrap();
+        let triggers = sim.physics.triggers();
+        let seen = sim.seen(blob_ref, &triggers[&blob_ref.sight_circle]);
+        let foods: Vec<_> = seen.iter()
+            .filter_map(|&(object, _, _)| match object { CircleObject::Food(food) => Some(food), _ => None })
+            .collect();
+        assert_eq!(foods, vec![near, far]);
+
+        //  the foods are 45 degrees to either side, so only the
+        //  falloff turns the blob toward the nearer one, upward
+        let direction = blob_ref.prepare_step(&seen, sim.sight_falloff).target_direction.unwrap();
+        assert!(direction.y < 0.);
+        let direction = blob_ref.prepare_step(&seen, 0.).target_direction.unwrap();
+        assert!(direction.y.abs() < 1e-5);
+        assert_eq!(ColorSteering::default().decide(&blob_ref.sense(&seen)).target_direction, Some(direction));
+    }
+
+    #[test]
+    fn test_blob_sense() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)).sight_depth(50.).favorite_color(Food::COLOR));
+        let other = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 75.)));
+        let near = sim.insert_food(Vector2::new(120., 100.));
+        let far = sim.insert_food(Vector2::new(100., 140.));
+        let blob_ref = sim.get_blob_mut(blob).unwrap();
+        blob_ref.direction = Vector2::new(1., 0.);
+        blob_ref.hunger = blob_ref.genome.max_hunger / 4.;
+
+        let blob_ref = sim.get_blob(blob).unwrap();
+        let seen = [
+            (CircleObject::Blob(blob), blob_ref.color, blob_ref.pos()),
+            (CircleObject::Food(far), Food::COLOR, Vector2::new(100., 140.)),
+            (CircleObject::Food(near), Food::COLOR, Vector2::new(120., 100.)),
+            (CircleObject::Blob(other), Food::COLOR, Vector2::new(100., 75.)),
+        ];
+        let perception = blob_ref.sense(&seen);
+        assert_eq!(perception.hunger_ratio, 0.25);
+        assert_eq!(perception.nearest_food, Some(Sensed { offset: Vector2::new(20., 0.), distance: 0.4, angle: 0. }));
+        let nearest_blob = perception.nearest_blob.unwrap();
+        assert_eq!(nearest_blob.offset, Vector2::new(0., -25.));
+        assert_eq!(nearest_blob.distance, 0.5);
+        assert!((nearest_blob.angle + 0.5).abs() < 1e-6);
+        //  the blob itself is not counted
+        assert_eq!(perception.color_similarity, 1.);
+
+        let perception = blob_ref.sense(&seen[..1]);
+        assert_eq!(perception.nearest_food, None);
+        assert_eq!(perception.nearest_blob, None);
+        assert_eq!(perception.color_similarity, 0.);
+    }
+
+    #[test]
+    fn test_food_is_eaten_once() {
+        let mut sim = Simulation::new(Vector2::new(100., 100.));
+        //  harmless blobs, so they do not fight over the food
+        let blob = || BlobBuilder::new().pos(Vector2::new(50., 50.)).attack(0.);
+        let a = sim.spawn_blob(blob());
+        let b = sim.spawn_blob(blob());
+        sim.get_blob_mut(a).unwrap().hunger = 5.;
+        sim.get_blob_mut(b).unwrap().hunger = 5.;
+        sim.insert_food(Vector2::new(50., 50.));
+
+        sim.step(0.01);
+        let fed = [a, b].iter().filter(|&&key| sim.get_blob(key).unwrap().hunger < 5.).count();
+        assert_eq!(fed, 1);
+    }
+
+    #[test]
+    fn test_well_fed_blob_splits() {
+        let mut sim = Simulation::new(Vector2::new(100., 100.));
+        sim.reproduction = ReproductionConfig {
+            mode: ReproductionMode::Asexual,
+            hunger_threshold: 0.5,
+            well_fed_time: 1.,
+            mutation_rate: 0.,
+        };
+        let parent = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(50., 50.)).speed(0.).max_hunger(10.).attack(0.));
+
+        for _ in 0..3 { sim.step(0.25); }
+        assert_eq!(sim.blobs.len(), 1);
+        sim.step(0.25);
+        assert_eq!(sim.blobs.len(), 2);
+
+        //  the child inherits the parent's stats and is fully registered
+        let (child_key, child) = sim.blobs.iter().find(|(&key, _)| key != parent).unwrap();
+        assert_eq!(child.genome, sim.blobs[parent].genome);
+        assert_eq!(child.genome.max_hunger, 10.);
+        assert_eq!(child.hunger, 0.);
+        assert!(matches!(sim.objects.get(&child.circle), Some(&CircleObject::Blob(key)) if key == *child_key));
+        assert!(matches!(sim.objects.get(&child.sight_circle), Some(&CircleObject::BlobSight(key)) if key == *child_key));
+        assert_eq!(sim.physics.circles.len(), 4);
+
+        //  the child knows where it came from
+        assert_eq!(child.parents, vec![parent]);
+        assert_eq!(child.generation, 1);
+        assert_eq!(sim.blobs[parent].generation, 0);
+        assert_eq!(sim.descendants(parent), vec![*child_key]);
+        assert_eq!(sim.descendants(*child_key), vec![]);
+    }
+
+    #[test]
+    fn test_touching_blobs_mate() {
+        let mut sim = Simulation::new(Vector2::new(200., 200.));
+        sim.reproduction = ReproductionConfig {
+            mode: ReproductionMode::Sexual,
+            hunger_threshold: 0.5,
+            well_fed_time: 1.,
+            mutation_rate: 0.,
+        };
+        let blob = |x, y| BlobBuilder::new().pos(Vector2::new(x, y)).radius(15.).speed(0.).attack(0.);
+        let parents = [sim.spawn_blob(blob(40., 50.).max_hunger(10.)), sim.spawn_blob(blob(60., 50.).max_hunger(20.))];
+        let loner = sim.spawn_blob(blob(150., 150.));
+        for &key in parents.iter().chain(&[loner]) {
+            sim.get_blob_mut(key).unwrap().well_fed_time = 1.;
+        }
+
+        sim.step(0.01);
+        assert_eq!(sim.blobs.len(), 4);
+        let child = sim.blobs.iter()
+            .find(|(&key, _)| !parents.contains(&key) && key != loner)
+            .map(|(_, blob)| blob).unwrap();
+        assert_eq!(child.pos(), Vector2::new(50., 50.));
+        assert!(child.genome.max_hunger == 10. || child.genome.max_hunger == 20.);
+        for &key in &parents {
+            assert_eq!(sim.get_blob(key).unwrap().well_fed_time, 0.);
+        }
+        //  without a mate, the loner waits
+        assert!(sim.get_blob(loner).unwrap().well_fed_time > 1.);
+    }
+
+    #[test]
+    fn test_config_populates_simulation() {
+        let sim = Simulation::with_config(SimulationConfig {
+            size: Vector2::new(200., 200.),
+            seed: Some(7),
+            food_spawn_rate: 10.,
+            initial_blobs: 3,
+            initial_foods: 5,
+            mutation_rate: 0.2,
+        });
+        assert_eq!(sim.blobs.len(), 3);
+        assert_eq!(sim.foods.len(), 5);
+        assert_eq!(sim.reproduction.mutation_rate, 0.2);
+    }
+
+    #[test]
+    fn test_foods_spawn_at_rate() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        sim.food_spawner = FoodSpawner::new(FoodSpawnStrategy::FixedRate { per_second: 10. });
+        for _ in 0..10 { sim.step(0.1); }
+        assert_eq!(sim.foods.len(), 10);
+    }
+
+    #[test]
+    fn test_paused_simulation_stays() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)));
+        sim.step(0.1);
+        let pos = sim.get_blob(blob).unwrap().pos();
+
+        sim.set_paused(true);
+        sim.step(0.1);
+        assert_eq!(sim.get_blob(blob).unwrap().pos(), pos);
+
+        sim.set_paused(false);
+        sim.set_time_scale(-1.);
+        assert_eq!(sim.time_scale(), 0.);
+        sim.step(0.1);
+        assert_eq!(sim.get_blob(blob).unwrap().pos(), pos);
+    }
+
+    #[test]
+    fn test_starving_blob_dies() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)).max_hunger(1.));
+
+        sim.step(0.6);
+        assert_eq!(sim.take_events(), vec![]);
+        sim.step(0.6);
+        assert_eq!(sim.take_events(), vec![SimulationEvent::Died { key: blob, cause: DeathCause::Starved }]);
+        assert!(sim.get_blob(blob).is_none());
+        assert_eq!(sim.take_events(), vec![]);
+    }
+
+    #[cfg(feature = "save")]
+    #[test]
+    fn test_save_load_round_trip() {
+        let mut sim = Simulation::with_config(SimulationConfig {
+            size: Vector2::new(400., 400.),
+            seed: Some(7),
+            food_spawn_rate: 5.,
+            initial_blobs: 10,
+            initial_foods: 30,
+            ..SimulationConfig::default()
+        });
+        for _ in 0..30 { sim.step(0.1); }
+
+        let mut buffer = vec![];
+        sim.save(&mut buffer).unwrap();
+        let mut loaded = Simulation::load(&buffer[..]).unwrap();
+        for _ in 0..30 {
+            sim.step(0.1);
+            loaded.step(0.1);
+        }
+
+        let positions = |sim: &Simulation| -> Vec<_> { sim.blobs.iter().map(|(&key, blob)| (key, blob.pos())).collect() };
+        let foods = |sim: &Simulation| -> Vec<_> { sim.foods.iter().map(|(&key, food)| (key, food.pos)).collect() };
+        assert_eq!(positions(&loaded), positions(&sim));
+        assert_eq!(foods(&loaded), foods(&sim));
+        assert_eq!(loaded.tick(), sim.tick());
+        //  new keys do not alias existing ones
+        let food = loaded.insert_food(Vector2::new(1., 1.));
+        assert_eq!(loaded.foods.len(), sim.foods.len() + 1);
+        assert!(loaded.get_food(food).is_some());
+    }
+
+    #[test]
+    fn test_replay_matches_recording() {
+        let mut log = ReplayLog::new(SimulationConfig {
+            size: Vector2::new(400., 400.),
+            seed: Some(7),
+            food_spawn_rate: 5.,
+            initial_blobs: 5,
+            initial_foods: 30,
+            ..SimulationConfig::default()
+        });
+        let mut sim = log.start();
+        for i in 0..100 {
+            if i % 10 == 0 {
+                log.input(&mut sim, Input::SpawnBlob(BlobBuilder::new().pos(Vector2::new(200., 200.))));
+            }
+            if i == 50 {
+                let pos = Vector2::new(200., 200.);
+                log.input(&mut sim, Input::Select { pos });
+                let (blobs, _) = sim.select(pos);
+                for blob in blobs {
+                    log.input(&mut sim, Input::Move { blob, pos: Vector2::new(50., 50.) });
+                }
+            }
+            log.step(&mut sim, 1. / 30.);
+        }
+
+        let replayed = Simulation::replay(&log);
+        let positions = |sim: &Simulation| -> Vec<_> { sim.blobs.iter().map(|(&key, blob)| (key, blob.pos())).collect() };
+        assert_eq!(replayed.tick(), sim.tick());
+        assert_eq!(positions(&replayed), positions(&sim));
+    }
+
+    #[test]
+    fn test_population_is_culled_to_max() {
+        let mut sim = Simulation::new_seeded(Vector2::new(1000., 1000.), 7);
+        sim.population.max_population = Some(3);
+        //  the closer to the corner, the less fit
+        sim.population.fitness = |blob| blob.pos().x;
+        let blobs: Vec<_> = (0..5)
+            .map(|i| sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100. + 200. * i as f32, 500.)).attack(0.)))
+            .collect();
+
+        sim.step(0.01);
+        assert_eq!(sim.blobs.len(), 3);
+        assert_eq!(sim.take_events(), vec![
+            SimulationEvent::Died { key: blobs[0], cause: DeathCause::Culled },
+            SimulationEvent::Died { key: blobs[1], cause: DeathCause::Culled },
+        ]);
+        assert!(blobs[2..].iter().all(|&blob| sim.get_blob(blob).is_some()));
+    }
+
+    #[test]
+    fn test_nearest_food() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)));
+        assert_eq!(sim.nearest_food(blob), None);
+
+        sim.insert_food(Vector2::new(10., 10.));
+        let near = sim.insert_food(Vector2::new(130., 90.));
+        assert_eq!(sim.nearest_food(blob), Some(near));
+
+        sim.remove_blob(blob);
+        assert_eq!(sim.nearest_food(blob), None);
+    }
+
+    #[test]
+    fn test_clear_foods() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)));
+        for i in 0..3 {
+            sim.insert_food(Vector2::new(20. * i as f32, 10.));
+        }
+        assert_eq!(sim.blob_count(), 1);
+        assert_eq!(sim.food_count(), 3);
+
+        sim.clear_foods();
+        assert_eq!(sim.food_count(), 0);
+        assert_eq!(sim.blob_count(), 1);
+        assert!(sim.get_blob(blob).is_some());
+        //  only the blob's body and sight are left
+        assert_eq!(sim.objects.len(), 2);
+        assert_eq!(sim.physics.circles.len(), 2);
+        sim.step(0.1);
+    }
+
+    #[test]
+    fn test_old_food_despawns() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        sim.food_lifetime = Some(1.);
+        let old = sim.insert_food(Vector2::new(50., 50.));
+        sim.step(0.6);
+        let new = sim.insert_food(Vector2::new(150., 150.));
+        assert_eq!(sim.get_food(old).unwrap().age, 0.6);
+
+        sim.step(0.6);
+        assert!(sim.get_food(old).is_none());
+        assert!(sim.get_food(new).is_some());
+        assert_eq!(sim.take_events(), vec![SimulationEvent::FoodDespawned { food: old }]);
+        assert_eq!(sim.physics.circles.len(), 1);
+
+        sim.step(0.6);
+        assert_eq!(sim.food_count(), 0);
+    }
+
+    #[test]
+    fn test_cull_to_keeps_fittest() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let hungers = [3., 0., 4., 1., 2.];
+        let blobs: Vec<_> = hungers.iter()
+            .map(|&hunger| {
+                let key = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)));
+                sim.get_blob_mut(key).unwrap().hunger = hunger;
+                key
+            })
+            .collect();
+
+        sim.cull_to(2, |blob| -blob.hunger);
+        let mut left: Vec<_> = sim.blobs.keys().collect();
+        left.sort();
+        let mut fittest = vec![blobs[1], blobs[3]];
+        fittest.sort();
+        assert_eq!(left, fittest);
+        assert_eq!(sim.take_events().len(), 3);
+
+        sim.cull_to(5, |blob| -blob.hunger);
+        assert_eq!(sim.blobs.len(), 2);
+        assert_eq!(sim.take_events(), vec![]);
+    }
+
+    #[test]
+    fn test_set_size_moves_border() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        sim.border_mode = BorderMode::Clamp;
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(150., 100.)).speed(100.));
+        sim.get_blob_mut(blob).unwrap().direction = Vector2::new(1., 0.);
+
+        sim.step(0.1);
+        assert!(sim.get_blob(blob).unwrap().pos().x > 150.);
+        sim.set_size(Vector2::new(100., 200.), false);
+        sim.step(0.1);
+        assert_eq!(sim.get_blob(blob).unwrap().pos().x, 100.);
+    }
+
+    #[test]
+    fn test_set_size_rescales() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(150., 100.)));
+        let food = sim.insert_food(Vector2::new(50., 20.));
+
+        sim.set_size(Vector2::new(400., 100.), true);
+        assert_eq!(sim.get_blob(blob).unwrap().pos(), Vector2::new(300., 50.));
+        assert_eq!(sim.get_food(food).unwrap().pos(), Vector2::new(100., 10.));
+        assert_eq!(sim.physics.circles[sim.get_food(food).unwrap().circle].center, Vector2::new(100., 10.));
+    }
+
+    #[test]
+    fn test_query_region() {
+        let mut sim = Simulation::new_seeded(Vector2::new(400., 400.), 7);
+        let inside = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)));
+        //  overlaps the rectangle, but its center is outside
+        sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(205., 100.)));
+        sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(300., 300.)));
+        let food_inside = sim.insert_food(Vector2::new(50., 150.));
+        sim.insert_food(Vector2::new(250., 50.));
+        sim.insert_wall(Vector2::new(0., 0.), Vector2::new(20., 20.));
+        let circles = sim.physics.circles.len();
+
+        let (blobs, foods) = sim.query_region(Vector2::new(0., 0.), Vector2::new(200., 200.));
+        assert_eq!(blobs, vec![inside]);
+        assert_eq!(foods, vec![food_inside]);
+        assert_eq!(sim.physics.circles.len(), circles);
+        let (blobs, foods) = sim.query_region(Vector2::new(350., 0.), Vector2::new(400., 400.));
+        assert!(blobs.is_empty() && foods.is_empty());
+        //  box selection is the same query
+        assert_eq!(sim.select_rect(Vector2::new(0., 0.), Vector2::new(200., 200.)), (vec![inside], vec![food_inside]));
+    }
+
+    #[test]
+    fn test_constant_speed_turning() {
+        let mut sim = Simulation::new_seeded(Vector2::new(400., 400.), 7);
+        sim.turning = Turning::ConstantSpeed;
+        let blob = sim.spawn_blob(BlobBuilder::new()
+            .pos(Vector2::new(200., 200.))
+            .pov(180.)
+            .speed(0.)
+            .rotation_speed(1.)
+            .steering(Steering::NearestFood));
+        sim.get_blob_mut(blob).unwrap().direction = Vector2::new(1., 0.);
+        //  straight below the blob, a quarter turn away
+        sim.insert_food(Vector2::new(200., 250.));
+
+        sim.step(0.5);
+        let angle = math::signed_angle_vector2(Vector2::new(1., 0.), sim.get_blob(blob).unwrap().direction());
+        assert!((angle - 0.5f32.to_degrees()).abs() < 1e-2, "{}", angle);
+    }
+
+    #[test]
+    fn test_bounce_off_corner() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(199., 199.)).speed(100.));
+        let direction = Vector2::new(1., 1.).normalized();
+        sim.get_blob_mut(blob).unwrap().direction = direction;
+
+        sim.step(0.05);
+        let blob = sim.get_blob(blob).unwrap();
+        assert_eq!(blob.pos(), Vector2::new(200., 200.));
+        assert_eq!(blob.direction(), direction * -1.);
+    }
+
+    #[test]
+    fn test_blob_snapshot_follows_blob() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)).speed(20.).max_hunger(10.));
+        sim.get_blob_mut(blob).unwrap().direction = Vector2::new(1., 0.);
+
+        sim.step(0.5);
+        let snapshot = sim.blob_snapshot(blob).unwrap();
+        let blob_ref = sim.get_blob(blob).unwrap();
+        assert_eq!(snapshot.pos, blob_ref.pos());
+        assert!(snapshot.pos.x > 100.);
+        assert_eq!(snapshot.speed, 20.);
+        assert_eq!(snapshot.alive_time, 0.5);
+        assert!(snapshot.hunger > 0.);
+        assert_eq!(snapshot.hunger_ratio, snapshot.hunger / 10.);
+
+        sim.remove_blob(blob);
+        assert_eq!(sim.blob_snapshot(blob), None);
+    }
+
+    #[test]
+    fn test_blob_does_not_enter_wall() {
+        let mut sim = Simulation::new_seeded(Vector2::new(400., 200.), 7);
+        let wall = sim.insert_wall(Vector2::new(200., 0.), Vector2::new(220., 200.));
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(150., 100.)).speed(100.));
+        sim.get_blob_mut(blob).unwrap().direction = Vector2::new(1., 0.);
+
+        for _ in 0..20 {
+            sim.step(0.05);
+            let blob = sim.get_blob(blob).unwrap();
+            let aabb = &sim.physics.aabbs[wall];
+            let closest = aabb.closest_point(blob.pos());
+            assert!((blob.pos() - closest).length() >= blob.radius() - 1e-3, "{:?}", blob.pos());
+        }
+        let blob = sim.get_blob(blob).unwrap();
+        assert!(blob.pos().x < 200.);
+        assert!(blob.direction().x < 0.);
+    }
+
+    #[test]
+    fn test_migrate_blob() {
+        let mut from = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let mut to = Simulation::new_seeded(Vector2::new(400., 100.), 8);
+        to.spawn_blob(BlobBuilder::new().pos(Vector2::new(10., 10.)));
+        let blob = from.spawn_blob(BlobBuilder::new().pos(Vector2::new(50., 100.)).speed(30.).name("Traveler"));
+        from.get_blob_mut(blob).unwrap().hunger = 2.;
+
+        let migrated = from.migrate_to(&mut to, blob).unwrap();
+        assert!(from.get_blob(blob).is_none());
+        assert!(from.objects.is_empty());
+        assert!(from.physics.circles.is_empty());
+
+        let blob = to.get_blob(migrated).unwrap();
+        assert_eq!(blob.name.as_deref(), Some("Traveler"));
+        assert_eq!(blob.hunger, 2.);
+        assert_eq!(blob.pos(), Vector2::new(100., 50.));
+        for &circle in &[blob.circle, blob.sight_circle] {
+            assert_eq!(to.physics.circles[circle].center, blob.pos());
+            assert!(to.objects.contains_key(&circle));
+        }
+        assert_eq!(to.physics.circles[blob.circle].radius, blob.radius());
+        assert_eq!(to.physics.circles[blob.sight_circle].radius, blob.sight_depth());
+        assert_eq!(to.blobs.len(), 2);
+        assert!(from.migrate_to(&mut to, migrated).is_none());
+    }
+
+    #[test]
+    fn test_wrap_border() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        sim.border_mode = BorderMode::Wrap;
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(199., 100.)).speed(100.));
+        let direction = Vector2::new(1., 0.);
+        sim.get_blob_mut(blob).unwrap().direction = direction;
+
+        sim.step(0.05);
+        let blob = sim.get_blob(blob).unwrap();
+        assert!(blob.pos().x < 10., "{:?}", blob.pos());
+        assert_eq!(blob.pos().y, 100.);
+        assert_eq!(blob.direction(), direction);
+    }
+
+    #[test]
+    fn test_environment_cycle() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        sim.environment.day_length = 4.;
+        sim.environment.amplitude = 1.;
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)).speed(20.).sight_depth(0.));
+        sim.step(2.);
+        assert_eq!(sim.environment.time_of_day, 0.5);
+        assert!((sim.environment.temperature - 1.).abs() < 1e-5);
+        sim.step(2.);
+        assert_eq!(sim.environment.time_of_day, 0.);
+        assert!((sim.environment.temperature + 1.).abs() < 1e-5);
+
+        //  only the cold slows blobs down
+        let blob_ref = sim.get_blob(blob).unwrap();
+        let at = |temperature| Environment { temperature, ..sim.environment };
+        assert_eq!(blob_ref.effective_speed(&at(-1.)), 10.);
+        assert_eq!(blob_ref.effective_speed(&at(0.)), 20.);
+        assert_eq!(blob_ref.effective_speed(&at(1.)), 20.);
+        assert!((sim.physics.circles[blob_ref.circle].velocity.length() - 10.).abs() < 1e-3);
+
+        //  and only the heat makes them hungrier
+        let hunger_gained = |time_of_day| {
+            let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+            sim.environment.day_length = 4.;
+            sim.environment.amplitude = 1.;
+            sim.environment.time_of_day = time_of_day;
+            let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)).speed(20.).sight_depth(0.));
+            let hunger = sim.get_blob(blob).unwrap().hunger;
+            sim.step(1.);
+            sim.get_blob(blob).unwrap().hunger - hunger
+        };
+        //  stepping into noon, and into midnight
+        let hot = hunger_gained(0.25);
+        let cold = hunger_gained(0.75);
+        assert!(cold > 0.);
+        assert!((hot - 1.5 * cold).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_hunger_rate_starves_faster() {
+        let starve_time = |hunger_rate| {
+            let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+            //  a metabolism that only depends on the hunger rate
+            sim.metabolism.size_cost = 0.;
+            sim.metabolism.speed_cost = 0.;
+            let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)).speed(0.).max_hunger(4.).hunger_rate(hunger_rate));
+            let mut steps = 0;
+            while sim.get_blob(blob).is_some() {
+                sim.step(0.01);
+                steps += 1;
+            }
+            steps as f32
+        };
+
+        let default = starve_time(1.);
+        let fast = starve_time(2.);
+        assert!((fast - default / 2.).abs() <= 1., "{} {}", fast, default);
+    }
+
+    #[test]
+    fn test_fast_blob_starves_first() {
+        let mut sim = Simulation::new_seeded(Vector2::new(1000., 1000.), 7);
+        //  harmless and blind, so they only starve
+        let blob = |x, speed| BlobBuilder::new().pos(Vector2::new(x, 500.)).speed(speed).sight_depth(0.).attack(0.);
+        let fast = sim.spawn_blob(blob(250., 120.));
+        let slow = sim.spawn_blob(blob(750., 10.));
+
+        while sim.get_blob(fast).is_some() {
+            sim.step(0.1);
+        }
+        assert!(sim.get_blob(slow).is_some());
+        assert!(sim.take_events().contains(&SimulationEvent::Died { key: fast, cause: DeathCause::Starved }));
+    }
+
+    #[test]
+    fn test_old_blob_dies() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)).max_lifespan(1.));
+
+        sim.step(0.6);
+        assert_eq!(sim.take_events(), vec![]);
+        sim.step(0.6);
+        assert_eq!(sim.take_events(), vec![SimulationEvent::Died { key: blob, cause: DeathCause::OldAge }]);
+        assert!(sim.get_blob(blob).is_none());
+        assert_eq!(sim.foods.len(), 1);
+    }
+
+    #[test]
+    fn test_observers_see_events() {
+        use std::{cell::Cell, rc::Rc};
+
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(50., 50.)));
+        sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(150., 150.)));
+        sim.insert_food(Vector2::new(50., 50.));
+        sim.insert_food(Vector2::new(150., 150.));
+        let ate = Rc::new(Cell::new(0));
+        let events = Rc::new(Cell::new(0));
+        {
+            let ate = ate.clone();
+            sim.on_event(Box::new(move |event| if let SimulationEvent::Ate { .. } = event {
+                ate.set(ate.get() + 1);
+            }));
+        }
+        {
+            let events = events.clone();
+            sim.on_event(Box::new(move |_| events.set(events.get() + 1)));
+        }
+
+        sim.step(0.01);
+        assert_eq!(ate.get(), 2);
+        assert_eq!(events.get(), sim.take_events().len());
+    }
+
+    #[test]
+    fn test_find_blobs_by_name() {
+        let mut sim = Simulation::new(Vector2::new(200., 200.));
+        let a = sim.spawn_blob(BlobBuilder::new().name("Bob"));
+        sim.spawn_blob(BlobBuilder::new().name("Alice"));
+        sim.spawn_blob(BlobBuilder::new());
+        let b = sim.spawn_blob(BlobBuilder::new().name("Bob"));
+
+        assert_eq!(sim.find_blobs_by_name("Bob"), vec![a, b]);
+        assert_eq!(sim.find_blobs_by_name("Carol"), vec![]);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut sim = Simulation::with_config(SimulationConfig {
+            size: Vector2::new(200., 200.),
+            seed: Some(7),
+            initial_blobs: 5,
+            initial_foods: 10,
+            ..SimulationConfig::default()
+        });
+        sim.step(0.1);
+        let old = sim.blobs.keys().next().unwrap();
+
+        sim.reset();
+        assert_eq!(sim.blobs.len(), 0);
+        assert_eq!(sim.foods.len(), 0);
+        assert_eq!(sim.objects.len(), 0);
+        assert_eq!(sim.physics.circles.len(), 0);
+        assert_eq!(sim.take_events(), vec![]);
+        assert_eq!(sim.size(), Vector2::new(200., 200.));
+
+        let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)));
+        assert!(sim.get_blob(old).is_none());
+        assert_eq!(sim.physics.circles.len(), 2);
+        sim.step(0.1);
+        assert!(sim.get_blob(blob).is_some());
+    }
+
+    #[test]
+    fn test_subdivided_step_matches_small_steps() {
+        let run = |step: &dyn Fn(&mut Simulation)| {
+            let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+            let blob = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(50., 100.)).sight_depth(0.));
+            sim.get_blob_mut(blob).unwrap().direction = Vector2::new(1., 0.);
+            step(&mut sim);
+            sim.get_blob(blob).unwrap().pos()
+        };
+
+        let subdivided = run(&|sim| sim.step_subdivided(1., 0.1));
+        let small = run(&|sim| for _ in 0..10 { sim.step(0.1); });
+        assert!((subdivided - small).length() < 0.01, "{:?} {:?}", subdivided, small);
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 7);
+        let stats = sim.stats();
+        assert_eq!(stats.blob_count, 0);
+        assert_eq!(stats.speed, TraitStats::default());
+        assert_eq!(stats.max_alive_time, None);
+
+        sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(20., 20.)).speed(10.).attack(1.));
+        let old = sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(100., 100.)).speed(20.).attack(2.));
+        sim.spawn_blob(BlobBuilder::new().pos(Vector2::new(180., 180.)).speed(60.).attack(3.));
+        sim.insert_food(Vector2::new(20., 180.));
+        sim.get_blob_mut(old).unwrap().alive_time = 6.;
+
+        let stats = sim.stats();
+        assert_eq!(stats.blob_count, 3);
+        assert_eq!(stats.food_count, 1);
+        assert_eq!(stats.speed, TraitStats { mean: 30., min: 10., max: 60. });
+        assert_eq!(stats.attack, TraitStats { mean: 2., min: 1., max: 3. });
+        assert_eq!(stats.mean_alive_time, 2.);
+        assert_eq!(stats.max_alive_time, Some((old, 6.)));
+    }
+
+    #[test]
+    fn test_headless_run() {
+        let mut sim = Simulation::with_config(SimulationConfig {
+            size: Vector2::new(400., 300.),
+            seed: Some(7),
+            food_spawn_rate: 5.,
+            initial_blobs: 20,
+            initial_foods: 50,
+            ..SimulationConfig::default()
+        });
+        for _ in 0..1000 {
+            sim.step(1. / 60.);
+        }
+        assert_eq!(sim.objects.len(), sim.physics.circles.len());
+    }
+
+    #[test]
+    fn test_seeded_runs_match() {
+        let run = || {
+            let mut sim = Simulation::new_seeded(Vector2::new(200., 200.), 42);
+            sim.reproduction.well_fed_time = 0.5;
+            for _ in 0..10 {
+                let pos = random_vector2(sim.rng()) * sim.size();
+                sim.spawn_blob(BlobBuilder::new().pos(pos));
+                let pos = random_vector2(sim.rng()) * sim.size();
+                sim.insert_food(pos);
+            }
+            for _ in 0..200 {
+                sim.step(0.05);
+            }
+            format!("{:?} {:?}", sim.blobs.values().collect::<Vec<_>>(), sim.foods.values().collect::<Vec<_>>())
+        };
+        assert_eq!(run(), run());
+    }
+}
+
 pub mod prelude {
     pub use super::*;
 }
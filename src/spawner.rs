@@ -0,0 +1,127 @@
+//! Policies for adding food to a simulation over time.
+
+use rand::prelude::*;
+
+use raylib::prelude::*;
+
+use crate::math::gaussian;
+
+
+/// How a food spawner decides when and where foods appear.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub enum FoodSpawnStrategy {
+    /// Foods appear at random positions, at a steady rate.
+    FixedRate { per_second: f32 },
+    /// Foods appear at random positions whenever there are fewer
+    /// than `count` of them.
+    TargetPopulation { count: usize },
+    /// Foods appear at a steady rate, scattered around a random
+    /// center with a standard deviation of `spread`. The center
+    /// moves after every `cluster_size` foods.
+    Clustered { per_second: f32, cluster_size: usize, spread: f32 },
+}
+
+/// Decides which foods to add to a simulation every step.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct FoodSpawner {
+    pub strategy: FoodSpawnStrategy,
+    //  the part of the next food that has spawned so far
+    progress: f32,
+    //  the center of the current cluster and the foods left in it
+    cluster: Option<(Vector2, usize)>,
+}
+
+impl Default for FoodSpawner {
+    fn default() -> Self { Self::new(FoodSpawnStrategy::FixedRate { per_second: 0. }) }
+}
+
+impl FoodSpawner {
+    pub fn new(strategy: FoodSpawnStrategy) -> Self {
+        Self { strategy, progress: 0., cluster: None }
+    }
+
+    /// Returns the positions of the foods to add after `timestep`
+    /// seconds, when the simulation of the given size has `food_count`
+    /// foods.
+    pub fn spawn<R: Rng + ?Sized>(&mut self, timestep: f32, food_count: usize, size: Vector2, rng: &mut R) -> Vec<Vector2> {
+        let random_pos = |rng: &mut R| Vector2::new(rng.gen::<f32>() * size.x, rng.gen::<f32>() * size.y);
+        match self.strategy {
+            FoodSpawnStrategy::FixedRate { per_second } => {
+                (0..self.count(per_second, timestep)).map(|_| random_pos(rng)).collect()
+            },
+            FoodSpawnStrategy::TargetPopulation { count } => {
+                (food_count..count).map(|_| random_pos(rng)).collect()
+            },
+            FoodSpawnStrategy::Clustered { per_second, cluster_size, spread } => {
+                let mut ret = vec![];
+                for _ in 0..self.count(per_second, timestep) {
+                    let (center, left) = match self.cluster {
+                        Some((center, left)) if left > 0 => (center, left),
+                        _ => (random_pos(rng), cluster_size),
+                    };
+                    self.cluster = Some((center, left.saturating_sub(1)));
+                    let offset = Vector2::new(gaussian(rng), gaussian(rng)) * spread;
+                    let pos = center + offset;
+                    ret.push(Vector2::new(pos.x.max(0.).min(size.x), pos.y.max(0.).min(size.y)));
+                }
+                ret
+            },
+        }
+    }
+
+    /// Returns how many foods are due at `per_second` after `timestep`
+    /// seconds, and keeps the remainder for the next time.
+    fn count(&mut self, per_second: f32, timestep: f32) -> usize {
+        self.progress += per_second * timestep;
+        let count = self.progress.floor();
+        self.progress -= count;
+        count as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_fixed_rate_count() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut spawner = FoodSpawner::new(FoodSpawnStrategy::FixedRate { per_second: 10. });
+        let size = Vector2::new(100., 100.);
+        let mut count = 0;
+        //  100 seconds
+        for _ in 0..6000 {
+            count += spawner.spawn(1. / 60., count, size, &mut rng).len();
+        }
+        assert!((999..=1001).contains(&count), "{}", count);
+    }
+
+    #[test]
+    fn test_target_population_fills_up() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut spawner = FoodSpawner::new(FoodSpawnStrategy::TargetPopulation { count: 20 });
+        let size = Vector2::new(100., 100.);
+        assert_eq!(spawner.spawn(0.1, 5, size, &mut rng).len(), 15);
+        assert_eq!(spawner.spawn(0.1, 25, size, &mut rng).len(), 0);
+    }
+
+    #[test]
+    fn test_clustered_stays_in_bounds() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut spawner = FoodSpawner::new(FoodSpawnStrategy::Clustered { per_second: 100., cluster_size: 10, spread: 50. });
+        let size = Vector2::new(100., 100.);
+        let foods = spawner.spawn(1., 0, size, &mut rng);
+        assert_eq!(foods.len(), 100);
+        for pos in foods {
+            assert!(0. <= pos.x && pos.x <= size.x && 0. <= pos.y && pos.y <= size.y);
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::{FoodSpawner, FoodSpawnStrategy};
+}
@@ -0,0 +1,54 @@
+//! Statistics of a simulation's population.
+
+use crate::{
+    keyed_set::prelude::*,
+    simulation::Blob,
+};
+
+
+/// The spread of a numeric trait over the population.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TraitStats {
+    pub mean: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl TraitStats {
+    /// Summarize the values, or return zeros if there are none.
+    pub fn new<I: IntoIterator<Item = f32>>(values: I) -> Self {
+        let mut count = 0;
+        let mut sum = 0.;
+        let mut min = f32::INFINITY;
+        let mut max = f32::NEG_INFINITY;
+        for value in values {
+            count += 1;
+            sum += value;
+            min = min.min(value);
+            max = max.max(value);
+        }
+        if count == 0 { return Self::default(); }
+        Self { mean: sum / count as f32, min, max }
+    }
+}
+
+/// A snapshot of a simulation's population.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    pub blob_count: usize,
+    pub food_count: usize,
+
+    pub speed: TraitStats,
+    pub pov: TraitStats,
+    pub sight_depth: TraitStats,
+    pub attack: TraitStats,
+    pub defence: TraitStats,
+
+    pub mean_alive_time: f32,
+    /// The oldest blob and how long it has been alive.
+    pub max_alive_time: Option<(Key<Blob>, f32)>,
+}
+
+pub mod prelude {
+    pub use super::{Stats, TraitStats};
+}
@@ -0,0 +1,254 @@
+//! How blobs choose where to go from what they see.
+//!
+//! Blobs steer by the strategy their genome names, unless the
+//! simulation is given a strategy for every blob.
+//!
+//! # Example
+//!
+//! ```
+//! struct Up;
+//!
+//! impl SteeringStrategy for Up {
+//!     fn decide(&self, _: &Perception) -> BlobStep {
+//!         BlobStep::new(Some(Vector2::new(0., -1.)))
+//!     }
+//! }
+//!
+//! sim.steering = Some(Box::new(Up));
+//! ```
+
+use rand::prelude::*;
+
+use raylib::prelude::*;
+
+use crate::{
+    simulation::{color_similarity, Blob, BlobStep, CircleObject},
+    math::gaussian,
+};
+
+
+/// What a blob knows when it chooses where to go, from `Blob::sense`.
+pub struct Perception<'a> {
+    pub blob: &'a Blob,
+    /// The objects the blob sees, with their colors and positions.
+    pub seen: &'a [(CircleObject, Color, Vector2)],
+    /// The hunger relative to the max hunger, from 0 to 1 while alive.
+    pub hunger_ratio: f32,
+    pub nearest_food: Option<Sensed>,
+    pub nearest_blob: Option<Sensed>,
+    /// The mean similarity of the colors seen to the favorite color,
+    /// from -1 to 1, or 0 when nothing is seen.
+    pub color_similarity: f32,
+}
+
+/// Where a seen object is, relative to the blob.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sensed {
+    pub offset: Vector2,
+    /// The distance relative to the sight depth, from 0 to 1.
+    pub distance: f32,
+    /// The signed angle from the blob's direction, relative to
+    /// 180 degrees, in (-1, 1].
+    pub angle: f32,
+}
+
+/// A way for blobs to choose where to go.
+pub trait SteeringStrategy {
+    fn decide(&self, perception: &Perception) -> BlobStep;
+}
+
+/// Toward the things a blob sees in colors it likes, and away from
+/// those in colors it does not.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ColorSteering {
+    /// How much more nearer things count. Everything is weighed by
+    /// one minus its distance relative to the sight depth, to the power
+    /// of `falloff`, so things fade in as they come into sight. At 0
+    /// everything counts the same, which is the default.
+    pub falloff: f32,
+}
+
+impl SteeringStrategy for ColorSteering {
+    fn decide(&self, perception: &Perception) -> BlobStep {
+        let blob = perception.blob;
+        let genome = blob.genome();
+
+        let mut sum = Vector2::zero();
+        let mut count = 0.;
+        for (_, color, pos) in perception.seen {
+
+            let v = color_similarity(&genome.favorite_color, color);
+            let v = v * (if v > 0. { genome.color_attraction } else { genome.color_repulsion });
+
+            let offset = *pos - blob.pos();
+            if offset.length_sqr() != 0. {
+                let distance = if genome.sight_depth > 0. { (offset.length() / genome.sight_depth).min(1.) } else { 1. };
+                let v = v * (1. - distance).powf(self.falloff);
+                sum += offset.normalized() * v;
+                count += v.abs();
+            }
+        }
+
+        let target_direction = if count == 0. || sum.length_sqr() == 0. {
+            None
+        } else {
+            let d = (sum / count as f32).normalized();
+            Some(d)
+        };
+
+        BlobStep::new(target_direction)
+    }
+}
+
+/// Straight to the nearest food a blob sees, or by color when it
+/// sees no food.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NearestFoodSteering {
+    /// How to steer when no food is seen.
+    pub fallback: ColorSteering,
+}
+
+impl SteeringStrategy for NearestFoodSteering {
+    fn decide(&self, perception: &Perception) -> BlobStep {
+        match perception.nearest_food {
+            Some(food) => BlobStep::new(Some(food.offset.normalized())),
+            None => self.fallback.decide(perception),
+        }
+    }
+}
+
+/// A small feed-forward network, from what a blob senses to where
+/// it goes. Its weights are heritable.
+///
+/// The inputs go through a hidden layer with a tanh activation,
+/// and then to two outputs, the target direction.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "save", derive(serde::Serialize, serde::Deserialize))]
+pub struct Brain {
+    /// The weights into every hidden node and then into every output,
+    /// each node's bias after its weights.
+    weights: Vec<f32>,
+}
+
+impl Default for Brain {
+    /// A brain that never turns.
+    fn default() -> Self { Self { weights: vec![0.; Self::WEIGHTS] } }
+}
+
+impl Brain {
+    pub const INPUTS: usize = 6;
+    pub const HIDDEN: usize = 4;
+    pub const OUTPUTS: usize = 2;
+    /// How many weights a brain has, biases included.
+    pub const WEIGHTS: usize = (Self::INPUTS + 1) * Self::HIDDEN + (Self::HIDDEN + 1) * Self::OUTPUTS;
+
+    /// Create a brain from its weights, in the order `think` uses them.
+    pub fn new(weights: Vec<f32>) -> Self {
+        assert_eq!(weights.len(), Self::WEIGHTS, "a brain has {} weights", Self::WEIGHTS);
+        Self { weights }
+    }
+
+    pub fn weights(&self) -> &[f32] { &self.weights }
+
+    /// Run the network on the inputs.
+    pub fn think(&self, inputs: &[f32; Self::INPUTS]) -> [f32; Self::OUTPUTS] {
+        let (hidden_weights, output_weights) = self.weights.split_at((Self::INPUTS + 1) * Self::HIDDEN);
+        let mut hidden = [0.; Self::HIDDEN];
+        for (node, weights) in hidden.iter_mut().zip(hidden_weights.chunks(Self::INPUTS + 1)) {
+            *node = layer_node(weights, inputs).tanh();
+        }
+        let mut outputs = [0.; Self::OUTPUTS];
+        for (node, weights) in outputs.iter_mut().zip(output_weights.chunks(Self::HIDDEN + 1)) {
+            *node = layer_node(weights, &hidden);
+        }
+        outputs
+    }
+
+    /// Returns a copy of the brain with every weight moved by
+    /// a gaussian with a standard deviation of `rate`.
+    pub fn mutate<R: Rng + ?Sized>(&self, rng: &mut R, rate: f32) -> Brain {
+        Self { weights: self.weights.iter().map(|weight| weight + rate * gaussian(rng)).collect() }
+    }
+
+    /// Returns a brain that takes each weight from one of the parents.
+    pub fn crossover<R: Rng + ?Sized>(&self, other: &Brain, rng: &mut R) -> Brain {
+        let weights = self.weights.iter().zip(&other.weights)
+            .map(|(&a, &b)| if rng.gen() { a } else { b })
+            .collect();
+        Self { weights }
+    }
+}
+
+/// The weighted sum of the inputs, plus the bias after the weights.
+fn layer_node(weights: &[f32], inputs: &[f32]) -> f32 {
+    let (bias, weights) = weights.split_last().unwrap();
+    weights.iter().zip(inputs).map(|(weight, input)| weight * input).sum::<f32>() + bias
+}
+
+/// Where the brain in a blob's genome says to go.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NeuralSteering;
+
+impl NeuralSteering {
+    /// What the brain senses: the distance and angle to the nearest
+    /// food and to the nearest blob, the hunger ratio, and the color
+    /// similarity. Things that are not seen are as far as the blob
+    /// sees, and straight ahead.
+    pub fn inputs(perception: &Perception) -> [f32; Brain::INPUTS] {
+        let sensed = |sensed: Option<Sensed>| sensed.map_or((1., 0.), |sensed| (sensed.distance, sensed.angle));
+        let (food_distance, food_angle) = sensed(perception.nearest_food);
+        let (blob_distance, blob_angle) = sensed(perception.nearest_blob);
+        [food_distance, food_angle, blob_distance, blob_angle, perception.hunger_ratio, perception.color_similarity]
+    }
+}
+
+impl SteeringStrategy for NeuralSteering {
+    fn decide(&self, perception: &Perception) -> BlobStep {
+        let [x, y] = perception.blob.genome().brain.think(&Self::inputs(perception));
+        let direction = Vector2::new(x, y);
+        BlobStep::new(if direction.length_sqr() > 0. { Some(direction.normalized()) } else { None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_brain_think() {
+        let mut weights = vec![0.; Brain::WEIGHTS];
+        let hidden = (Brain::INPUTS + 1) * Brain::HIDDEN;
+        //  the first hidden node is the first input, the second is the
+        //  bias alone, and the rest are off
+        weights[0] = 1.;
+        weights[2 * (Brain::INPUTS + 1) - 1] = 0.5;
+        //  the first output is twice the first hidden node, and the
+        //  second output is the second hidden node plus 1
+        weights[hidden] = 2.;
+        weights[hidden + Brain::HIDDEN + 1 + 1] = 1.;
+        weights[hidden + 2 * (Brain::HIDDEN + 1) - 1] = 1.;
+        let brain = Brain::new(weights);
+
+        let [a, b] = brain.think(&[0.25, 3., -2., 0., 0., 1.]);
+        assert!((a - 2. * 0.25f32.tanh()).abs() < 1e-6);
+        assert!((b - (0.5f32.tanh() + 1.)).abs() < 1e-6);
+        assert_eq!(Brain::default().think(&[1.; Brain::INPUTS]), [0., 0.]);
+    }
+
+    #[test]
+    fn test_brain_mutate_and_crossover() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let a = Brain::default();
+        assert_eq!(a.mutate(&mut rng, 0.), a);
+        let b = a.mutate(&mut rng, 0.5);
+        assert_ne!(b, a);
+        let child = a.crossover(&b, &mut rng);
+        for (i, weight) in child.weights().iter().enumerate() {
+            assert!(*weight == a.weights()[i] || *weight == b.weights()[i]);
+        }
+    }
+}
+
+pub mod prelude {
+    pub use super::{Perception, Sensed, SteeringStrategy, ColorSteering, NearestFoodSteering, NeuralSteering, Brain};
+}
@@ -1,3 +1,10 @@
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
 use raylib::prelude::*;
 
 pub struct Window {
@@ -6,25 +13,74 @@ pub struct Window {
 }
 
 pub type DrawingContext<'a> = RaylibDrawHandle<'a>;
+/// Drawing in world coordinates, through a camera.
+pub type CameraDrawingContext<'a, 'b> = RaylibMode2D<'a, DrawingContext<'b>>;
 
 pub use raylib::prelude::MouseButton;
 pub use raylib::prelude::KeyboardKey;
 
+/// Whether the draw loop goes on after a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlFlow {
+    Continue,
+    /// Save the frame to a PNG at the path once it is drawn, and continue.
+    ///
+    /// raylib also saves a screenshot of its own on F12, so bind
+    /// this to another key.
+    Screenshot(PathBuf),
+    Exit,
+}
+
+/// Save what was drawn so far to a PNG.
+fn save_screen(handle: &RaylibHandle, thread: &RaylibThread, path: &Path) -> io::Result<()> {
+    if path.extension().map_or(true, |extension| extension != "png") {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "screenshots must be .png files"));
+    }
+    let path_str = path.to_str()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the path is not valid unicode"))?;
+    //  raylib does not report failures, so make sure the file can be written first
+    fs::File::create(path)?;
+    handle.load_image_from_screen(thread).export_image(path_str);
+    //  and that something was written to it after all
+    if fs::metadata(path)?.len() == 0 {
+        fs::remove_file(path)?;
+        return Err(io::Error::new(io::ErrorKind::Other, "raylib could not export the image"));
+    }
+    Ok(())
+}
+
 pub struct WindowConfig {
     pub width: u32,
     pub height: u32,
     pub title: &'static str,
+    /// The most frames a second, or `None` to draw as fast as possible.
+    ///
+    /// Capping the frame rate keeps the delta times of the draw loop
+    /// steady, which keeps the timesteps fed to a simulation steady.
+    pub target_fps: Option<u32>,
+    /// Whether the user can resize the window.
+    pub resizable: bool,
 }
 
 impl Window {
-    pub fn new(WindowConfig { width, height, title }: &WindowConfig) -> Self {
-        let (handle, thread) = raylib::init()
-            .title(title)
-            .size(*width as i32, *height as i32)
-            .build();
+    pub fn new(WindowConfig { width, height, title, target_fps, resizable }: &WindowConfig) -> Self {
+        let mut builder = raylib::init();
+        builder.title(title).size(*width as i32, *height as i32);
+        if *resizable {
+            builder.resizable();
+        }
+        let (mut handle, thread) = builder.build();
+        if let Some(fps) = target_fps {
+            handle.set_target_fps(*fps);
+        }
         Self { handle, thread }
     }
 
+    /// Cap the frame rate. See `WindowConfig::target_fps`.
+    pub fn set_target_fps(&mut self, fps: u32) {
+        self.handle.set_target_fps(fps);
+    }
+
     pub fn width(&self) -> u32 {
         self.handle.get_screen_width() as u32
     }
@@ -33,16 +89,172 @@ impl Window {
         self.handle.get_screen_height() as u32
     }
 
+    pub fn size(&self) -> Vector2 {
+        Vector2::new(self.width() as f32, self.height() as f32)
+    }
+
+    /// Whether the window was resized since the last frame.
+    pub fn was_resized(&self) -> bool {
+        self.handle.is_window_resized()
+    }
+
+    /// Save the framebuffer to a PNG. In the draw loop, return
+    /// `ControlFlow::Screenshot` instead, to save the frame after
+    /// it is drawn.
+    pub fn screenshot<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        save_screen(&self.handle, &self.thread, path.as_ref())
+    }
+
+    /// Call `draw` every frame until it returns `ControlFlow::Exit` or
+    /// the window closes, with the seconds since the previous frame,
+    /// which is 0 on the first one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// //  run for 600 frames, then quit
+    /// let mut frames = 0;
+    /// window.draw_loop(|draw, _| {
+    ///     draw.clear_background(Color::WHITE);
+    ///     frames += 1;
+    ///     if frames < 600 { ControlFlow::Continue } else { ControlFlow::Exit }
+    /// });
+    /// ```
     pub fn draw_loop<F>(&mut self, mut draw: F)
-    where F: FnMut(DrawingContext) {
+    where F: FnMut(&mut DrawingContext, f32) -> ControlFlow {
+        let mut last_frame_time = None;
         while !self.handle.window_should_close() {
-            draw(self.handle.begin_drawing(&self.thread));
+            let frame_time = Instant::now();
+            let delta_time = last_frame_time.map_or(0., |last| (frame_time - last).as_secs_f32());
+            last_frame_time = Some(frame_time);
+            let mut frame = self.handle.begin_drawing(&self.thread);
+            match draw(&mut frame, delta_time) {
+                ControlFlow::Continue => (),
+                ControlFlow::Screenshot(path) => {
+                    //  SAFETY: the frame is between BeginDrawing and EndDrawing,
+                    //  on the thread that owns the window, so the render batch
+                    //  is active. Drawing it now puts what is still batched,
+                    //  like text, in the saved frame.
+                    unsafe { raylib::ffi::rlDrawRenderBatchActive(); }
+                    if let Err(err) = save_screen(&frame, &self.thread, &path) {
+                        eprintln!("Could not save screenshot {}: {}", path.display(), err);
+                    }
+                },
+                ControlFlow::Exit => break,
+            }
         }
     }
 
     pub fn handle(&self) -> &RaylibHandle { &self.handle }
 }
 
+/// A view of the world that can be panned and zoomed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// The world point at the top left corner of the screen.
+    pub target: Vector2,
+    /// How many pixels a unit of the world takes.
+    pub zoom: f32,
+    //  the mouse position while panning
+    drag: Option<Vector2>,
+}
+
+impl Default for Camera {
+    fn default() -> Self { Self { target: Vector2::zero(), zoom: 1., drag: None } }
+}
+
+impl Camera {
+    pub const MIN_ZOOM: f32 = 0.1;
+    pub const MAX_ZOOM: f32 = 10.;
+    /// How much a notch of the mouse wheel zooms.
+    pub const ZOOM_STEP: f32 = 1.1;
+
+    pub fn new() -> Self { Self::default() }
+
+    pub fn screen_to_world(&self, p: Vector2) -> Vector2 { p / self.zoom + self.target }
+
+    pub fn world_to_screen(&self, p: Vector2) -> Vector2 { (p - self.target) * self.zoom }
+
+    /// Move the view by an offset in screen pixels.
+    pub fn pan(&mut self, offset: Vector2) {
+        self.target -= offset / self.zoom;
+    }
+
+    /// Multiply the zoom by `factor`, keeping the world point under
+    /// `screen_pos` in place.
+    pub fn zoom_at(&mut self, screen_pos: Vector2, factor: f32) {
+        let world_pos = self.screen_to_world(screen_pos);
+        self.zoom = (self.zoom * factor).max(Self::MIN_ZOOM).min(Self::MAX_ZOOM);
+        self.target = world_pos - screen_pos / self.zoom;
+    }
+
+    /// Go back to showing the world at 1:1 from its origin.
+    pub fn reset(&mut self) { *self = Self::default(); }
+
+    /// Pan while the right mouse button is down, and zoom toward the
+    /// cursor with the mouse wheel.
+    pub fn update(&mut self, handle: &RaylibHandle) {
+        let mouse_pos = handle.get_mouse_position();
+        if handle.is_mouse_button_down(MouseButton::MOUSE_RIGHT_BUTTON) {
+            if let Some(drag) = self.drag {
+                self.pan(mouse_pos - drag);
+            }
+            self.drag = Some(mouse_pos);
+        } else {
+            self.drag = None;
+        }
+
+        let wheel = handle.get_mouse_wheel_move() as f32;
+        if wheel != 0. {
+            self.zoom_at(mouse_pos, Self::ZOOM_STEP.powf(wheel));
+        }
+    }
+
+    /// Start drawing in world coordinates.
+    pub fn begin<'a, 'b>(&self, draw: &'a mut DrawingContext<'b>) -> CameraDrawingContext<'a, 'b> {
+        draw.begin_mode2D(Camera2D { offset: Vector2::zero(), target: self.target, rotation: 0., zoom: self.zoom })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vector2, b: Vector2) {
+        assert!((a - b).length() < 1e-3, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_screen_to_world_inverts_world_to_screen() {
+        let mut camera = Camera::new();
+        camera.pan(Vector2::new(30., -20.));
+        camera.zoom_at(Vector2::new(100., 50.), 2.5);
+        let p = Vector2::new(12., 345.);
+        assert_close(camera.screen_to_world(camera.world_to_screen(p)), p);
+        assert_close(camera.world_to_screen(camera.screen_to_world(p)), p);
+    }
+
+    #[test]
+    fn test_zoom_keeps_point_under_cursor() {
+        let mut camera = Camera::new();
+        let cursor = Vector2::new(200., 100.);
+        let before = camera.screen_to_world(cursor);
+        camera.zoom_at(cursor, 2.);
+        assert_close(camera.screen_to_world(cursor), before);
+        assert_eq!(camera.zoom, 2.);
+        assert_close(camera.screen_to_world(Vector2::zero()), Vector2::new(100., 50.));
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut camera = Camera::new();
+        camera.pan(Vector2::new(30., -20.));
+        camera.zoom_at(Vector2::new(100., 50.), 0.5);
+        camera.reset();
+        assert_eq!(camera, Camera::default());
+    }
+}
+
 pub mod prelude {
-    pub use super::{Window, DrawingContext, WindowConfig};
+    pub use super::{Window, DrawingContext, CameraDrawingContext, WindowConfig, ControlFlow, Camera};
 }
\ No newline at end of file